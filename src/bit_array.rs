@@ -0,0 +1,300 @@
+use super::{Chunk, ChunkRead, Index};
+use core::fmt::{self, Debug, Display};
+use core::iter::FromIterator;
+use core::ops::{BitAnd, BitOr, BitXor, Not, Range, RangeInclusive, RangeTo, Sub};
+
+/// Returns the number of chunks needed to store bits indices, i.e., the CHUNKS of `BitArray<bits, CHUNKS>`.
+pub const fn chunk_count_for(bits: usize) -> usize {
+    bits.div_ceil(usize::BITS as usize)
+}
+
+/// Set of indices in 0..BITS, stored inline in CHUNKS chunks. Never allocates, and is Copy.
+/// Stable Rust cannot compute CHUNKS from BITS, so it is given explicitly as `chunk_count_for(BITS)`,
+/// e.g., `BitArray<100, { chunk_count_for(100) }>`. Other values of CHUNKS fail to compile.
+///
+/// Unlike IndexSet, inserting an index outside 0..BITS does not grow the set. It fails instead.
+/// The const fn constructors and methods allow BitArrays in consts and statics, and as patterns.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BitArray<const BITS: usize, const CHUNKS: usize> {
+    // invariant: bits at positions BITS.. are unset
+    chunks: [Chunk; CHUNKS],
+}
+
+/// Error returned when inserting an index outside the capacity of a BitArray.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IndexOutOfBounds {
+    pub index: Index,
+    /// The BITS of the BitArray, i.e., index is not less than bits.
+    pub bits: usize,
+}
+
+///////////////////////////////////////////////////////////////////////
+
+impl<const BITS: usize, const CHUNKS: usize> BitArray<BITS, CHUNKS> {
+    const VALID_CHUNKS: () = assert!(
+        CHUNKS == chunk_count_for(BITS),
+        "CHUNKS of BitArray must be chunk_count_for(BITS)"
+    );
+
+    /// The empty set.
+    pub const fn new() -> Self {
+        let () = Self::VALID_CHUNKS;
+        Self { chunks: [0; CHUNKS] }
+    }
+    /// The set of all indices in 0..BITS.
+    pub const fn full() -> Self {
+        Self::new().inverted()
+    }
+    /// The set of the given indices. Panics if any index is not less than BITS.
+    pub const fn from_indices(indices: &[Index]) -> Self {
+        Self::new().with_indices(indices)
+    }
+    /// The set of indices in the given range. Panics if the range is nonempty and extends beyond BITS.
+    pub const fn from_range(range: Range<Index>) -> Self {
+        Self::new().with_range(range)
+    }
+    /// Creates a BitArray from its chunks. Panics if any bit at position BITS.. is set.
+    pub const fn from_chunks(chunks: [Chunk; CHUNKS]) -> Self {
+        let () = Self::VALID_CHUNKS;
+        if CHUNKS > 0 {
+            assert!(
+                chunks[CHUNKS - 1] & !Self::last_chunk_mask() == 0,
+                "chunks of BitArray contain indices beyond BITS"
+            );
+        }
+        Self { chunks }
+    }
+    /// Mask of the bits of the last chunk which represent indices in 0..BITS. Requires 0 < CHUNKS.
+    const fn last_chunk_mask() -> Chunk {
+        match BITS % usize::BITS as usize {
+            0 => !0,
+            bits => !(!0 << bits),
+        }
+    }
+    pub const fn as_chunks(&self) -> &[Chunk; CHUNKS] {
+        &self.chunks
+    }
+    /// Returns the BitArray's index capacity, i.e., ..BITS.
+    pub const fn capacity(&self) -> RangeTo<usize> {
+        ..BITS
+    }
+    pub const fn contains(&self, index: Index) -> bool {
+        index < BITS
+            && self.chunks[index / usize::BITS as usize] >> (index % usize::BITS as usize) & 1 == 1
+    }
+    /// Returns this set with index added. Panics if index is not less than BITS.
+    pub const fn with(mut self, index: Index) -> Self {
+        assert!(index < BITS, "index out of bounds of BitArray");
+        self.chunks[index / usize::BITS as usize] |= 1 << (index % usize::BITS as usize);
+        self
+    }
+    /// Returns this set with the given indices added. Panics if any index is not less than BITS.
+    pub const fn with_indices(mut self, indices: &[Index]) -> Self {
+        let mut i = 0;
+        while i < indices.len() {
+            self = self.with(indices[i]);
+            i += 1;
+        }
+        self
+    }
+    /// Returns this set with the indices in the given range added.
+    /// Panics if the range is nonempty and extends beyond BITS.
+    pub const fn with_range(mut self, range: Range<Index>) -> Self {
+        const B: usize = usize::BITS as usize;
+        if range.end <= range.start {
+            return self;
+        }
+        assert!(range.end <= BITS, "range out of bounds of BitArray");
+        let mut index = range.start;
+        while index < range.end {
+            // set the bits from index up to the end of its chunk, or the range
+            let idx_in_chunk = index % B;
+            let n = if B - idx_in_chunk < range.end - index {
+                B - idx_in_chunk
+            } else {
+                range.end - index
+            };
+            self.chunks[index / B] |= (!0 >> (B - n)) << idx_in_chunk;
+            index += n;
+        }
+        self
+    }
+    /// Returns this set with index removed.
+    pub const fn without(mut self, index: Index) -> Self {
+        if index < BITS {
+            self.chunks[index / usize::BITS as usize] &= !(1 << (index % usize::BITS as usize));
+        }
+        self
+    }
+    pub const fn union(mut self, other: Self) -> Self {
+        let mut i = 0;
+        while i < CHUNKS {
+            self.chunks[i] |= other.chunks[i];
+            i += 1;
+        }
+        self
+    }
+    pub const fn intersection(mut self, other: Self) -> Self {
+        let mut i = 0;
+        while i < CHUNKS {
+            self.chunks[i] &= other.chunks[i];
+            i += 1;
+        }
+        self
+    }
+    pub const fn symmetric_difference(mut self, other: Self) -> Self {
+        let mut i = 0;
+        while i < CHUNKS {
+            self.chunks[i] ^= other.chunks[i];
+            i += 1;
+        }
+        self
+    }
+    pub const fn difference(mut self, other: Self) -> Self {
+        let mut i = 0;
+        while i < CHUNKS {
+            self.chunks[i] &= !other.chunks[i];
+            i += 1;
+        }
+        self
+    }
+    /// Returns the complement of this set within 0..BITS.
+    pub const fn inverted(mut self) -> Self {
+        let mut i = 0;
+        while i < CHUNKS {
+            self.chunks[i] = !self.chunks[i];
+            i += 1;
+        }
+        if CHUNKS > 0 {
+            self.chunks[CHUNKS - 1] &= Self::last_chunk_mask();
+        }
+        self
+    }
+    /// Adds the given index to the set. Returns whether it was absent before i.e. the set has changed.
+    /// Returns an error, leaving the set unchanged, if index is not less than BITS.
+    pub fn try_insert(&mut self, index: Index) -> Result<bool, IndexOutOfBounds> {
+        if BITS <= index {
+            return Err(IndexOutOfBounds { index, bits: BITS });
+        }
+        let was_unset = !self.contains(index);
+        *self = self.with(index);
+        Ok(was_unset)
+    }
+    /// Adds the given index to the set. Returns whether it was absent before i.e. the set has changed.
+    /// Panics if index is not less than BITS. See try_insert.
+    pub fn insert(&mut self, index: Index) -> bool {
+        match self.try_insert(index) {
+            Ok(was_unset) => was_unset,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    /// Removes the given index from the set. Returns whether it was present before i.e. the set has changed.
+    pub fn remove(&mut self, index: Index) -> bool {
+        let was_set = self.contains(index);
+        *self = self.without(index);
+        was_set
+    }
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> Default for BitArray<BITS, CHUNKS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> Debug for BitArray<BITS, CHUNKS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> ChunkRead for BitArray<BITS, CHUNKS> {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.chunks.get(idx_of_chunk).copied()
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        CHUNKS
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> FromIterator<Index> for BitArray<BITS, CHUNKS> {
+    /// Panics if any index is not less than BITS.
+    fn from_iter<I: IntoIterator<Item = Index>>(into_iter: I) -> Self {
+        let mut m = Self::new();
+        for index in into_iter.into_iter() {
+            m.insert(index);
+        }
+        m
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> BitOr for BitArray<BITS, CHUNKS> {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> BitAnd for BitArray<BITS, CHUNKS> {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        self.intersection(other)
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> BitXor for BitArray<BITS, CHUNKS> {
+    type Output = Self;
+    fn bitxor(self, other: Self) -> Self {
+        self.symmetric_difference(other)
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> Sub for BitArray<BITS, CHUNKS> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self.difference(other)
+    }
+}
+impl<const BITS: usize, const CHUNKS: usize> Not for BitArray<BITS, CHUNKS> {
+    type Output = Self;
+    fn not(self) -> Self {
+        self.inverted()
+    }
+}
+
+/// An item of const_index_set!, i.e., an index, a range, or an inclusive range.
+/// Each kind has its own insert_into, s.t. the macro need not distinguish them.
+#[doc(hidden)]
+pub struct ConstItem<T>(pub T);
+
+impl ConstItem<Index> {
+    pub const fn insert_into<const BITS: usize, const CHUNKS: usize>(
+        self,
+        set: BitArray<BITS, CHUNKS>,
+    ) -> BitArray<BITS, CHUNKS> {
+        set.with(self.0)
+    }
+}
+impl ConstItem<Range<Index>> {
+    pub const fn insert_into<const BITS: usize, const CHUNKS: usize>(
+        self,
+        set: BitArray<BITS, CHUNKS>,
+    ) -> BitArray<BITS, CHUNKS> {
+        set.with_range(self.0)
+    }
+}
+impl ConstItem<RangeInclusive<Index>> {
+    pub const fn insert_into<const BITS: usize, const CHUNKS: usize>(
+        self,
+        set: BitArray<BITS, CHUNKS>,
+    ) -> BitArray<BITS, CHUNKS> {
+        let (start, end) = (*self.0.start(), *self.0.end());
+        if end < start {
+            return set;
+        }
+        // end < BITS unless this panics anyway, s.t. end + 1 cannot overflow
+        set.with(end).with_range(start..end)
+    }
+}
+
+impl Display for IndexOutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "index {} is out of bounds of BitArray with {} bits", self.index, self.bits)
+    }
+}
+impl std::error::Error for IndexOutOfBounds {}
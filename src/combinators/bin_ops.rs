@@ -1,12 +1,12 @@
-use crate::{BinChunkOp, Chunk};
+use crate::{BinChunkOp, Chunk, ChunkRead};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Or;
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Xor;
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct And;
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Without;
 
 //////////
@@ -15,6 +15,19 @@ pub struct Without;
 fn z(chunk: Option<Chunk>) -> Chunk {
     chunk.unwrap_or(0)
 }
+/// Index of the first chunk at or after idx_of_chunk that is nonzero in a or in b.
+pub(crate) fn next_nonzero_chunk_in_either<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
+    a: &A,
+    b: &B,
+    idx_of_chunk: usize,
+) -> Option<usize> {
+    let na = a.next_nonzero_chunk_from(idx_of_chunk).map(|(idx, _)| idx);
+    let nb = b.next_nonzero_chunk_from(idx_of_chunk).map(|(idx, _)| idx);
+    match [na, nb] {
+        [Some(na), Some(nb)] => Some(na.min(nb)),
+        [na, nb] => na.or(nb),
+    }
+}
 impl BinChunkOp for Or {
     fn combine_chunks(self, a: Option<Chunk>, b: Option<Chunk>) -> Option<Chunk> {
         if a.is_none() && b.is_none() {
@@ -26,6 +39,14 @@ impl BinChunkOp for Or {
     fn combine_zero_chunks_from_conservative(self, ncfa: usize, ncfb: usize) -> usize {
         ncfa.max(ncfb)
     }
+    fn combine_next_nonzero_chunk_from<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
+        self,
+        a: &A,
+        b: &B,
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        next_nonzero_chunk_in_either(a, b, idx_of_chunk)
+    }
 }
 impl BinChunkOp for Xor {
     fn combine_chunks(self, a: Option<Chunk>, b: Option<Chunk>) -> Option<Chunk> {
@@ -38,6 +59,14 @@ impl BinChunkOp for Xor {
     fn combine_zero_chunks_from_conservative(self, ncfa: usize, ncfb: usize) -> usize {
         ncfa.max(ncfb)
     }
+    fn combine_next_nonzero_chunk_from<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
+        self,
+        a: &A,
+        b: &B,
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        next_nonzero_chunk_in_either(a, b, idx_of_chunk)
+    }
 }
 impl BinChunkOp for And {
     fn combine_chunks(self, a: Option<Chunk>, b: Option<Chunk>) -> Option<Chunk> {
@@ -50,16 +79,36 @@ impl BinChunkOp for And {
     fn combine_zero_chunks_from_conservative(self, ncfa: usize, ncfb: usize) -> usize {
         ncfa.min(ncfb)
     }
+    fn combine_next_nonzero_chunk_from<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
+        self,
+        a: &A,
+        b: &B,
+        mut idx_of_chunk: usize,
+    ) -> Option<usize> {
+        // leapfrog until both readers have a nonzero chunk at the same index
+        loop {
+            let (na, _) = a.next_nonzero_chunk_from(idx_of_chunk)?;
+            let (nb, _) = b.next_nonzero_chunk_from(na)?;
+            if na == nb {
+                return Some(na);
+            }
+            idx_of_chunk = nb;
+        }
+    }
 }
 impl BinChunkOp for Without {
     fn combine_chunks(self, a: Option<Chunk>, b: Option<Chunk>) -> Option<Chunk> {
-        if let Some(a) = a {
-            Some(a & !(z(b)))
-        } else {
-            None
-        }
+        a.map(|a| a & !z(b))
     }
     fn combine_zero_chunks_from_conservative(self, ncfa: usize, _ncfb: usize) -> usize {
         ncfa
     }
+    fn combine_next_nonzero_chunk_from<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
+        self,
+        a: &A,
+        _b: &B,
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        a.next_nonzero_chunk_from(idx_of_chunk).map(|(idx, _)| idx)
+    }
 }
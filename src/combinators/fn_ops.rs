@@ -0,0 +1,110 @@
+use super::bin_ops::{And, Or, Without};
+use crate::{BinChunkOp, Chunk, ChunkRead, TernChunkOp};
+
+/// Declares for which inputs the function of a FnOp is guaranteed to return zero,
+/// s.t. the combination knows where its nonzero chunks may be.
+/// Declaring too many cases makes the combination incorrect. Declaring too few only makes it slower,
+/// with Both being the fewest cases that any FnOp must satisfy.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ZeroWhen {
+    /// f(0, 0) == 0, like Or.
+    Both,
+    /// f(0, b) == 0 and f(a, 0) == 0, like And.
+    Either,
+    /// f(0, b) == 0, like Without.
+    Left,
+    /// f(a, 0) == 0.
+    Right,
+}
+
+/// Combines two readers with a closure applied to each pair of chunks.
+#[derive(Debug, Copy, Clone)]
+pub struct FnOp<F: Fn(Chunk, Chunk) -> Chunk + Copy> {
+    pub f: F,
+    pub zero_when: ZeroWhen,
+}
+
+/// Combines three readers with a closure applied to each triple of chunks.
+/// The closure must return zero if all three chunks are zero.
+#[derive(Debug, Copy, Clone)]
+pub struct FnOp3<F: Fn(Chunk, Chunk, Chunk) -> Chunk + Copy> {
+    pub f: F,
+}
+
+//////////
+
+#[inline]
+fn z(chunk: Option<Chunk>) -> Chunk {
+    chunk.unwrap_or(0)
+}
+
+impl<F: Fn(Chunk, Chunk) -> Chunk + Copy> FnOp<F> {
+    pub fn new(zero_when: ZeroWhen, f: F) -> Self {
+        Self { f, zero_when }
+    }
+}
+impl<F: Fn(Chunk, Chunk) -> Chunk + Copy> BinChunkOp for FnOp<F> {
+    fn combine_chunks(self, a: Option<Chunk>, b: Option<Chunk>) -> Option<Chunk> {
+        let zero = match self.zero_when {
+            ZeroWhen::Both => a.is_none() && b.is_none(),
+            ZeroWhen::Either => a.is_none() || b.is_none(),
+            ZeroWhen::Left => a.is_none(),
+            ZeroWhen::Right => b.is_none(),
+        };
+        if zero {
+            None
+        } else {
+            Some((self.f)(z(a), z(b)))
+        }
+    }
+    fn combine_zero_chunks_from_conservative(self, ncfa: usize, ncfb: usize) -> usize {
+        match self.zero_when {
+            ZeroWhen::Both => ncfa.max(ncfb),
+            ZeroWhen::Either => ncfa.min(ncfb),
+            ZeroWhen::Left => ncfa,
+            ZeroWhen::Right => ncfb,
+        }
+    }
+    fn combine_next_nonzero_chunk_from<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
+        self,
+        a: &A,
+        b: &B,
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        // the zero-sized ops with the same zero cases know where to look
+        match self.zero_when {
+            ZeroWhen::Both => Or.combine_next_nonzero_chunk_from(a, b, idx_of_chunk),
+            ZeroWhen::Either => And.combine_next_nonzero_chunk_from(a, b, idx_of_chunk),
+            ZeroWhen::Left => Without.combine_next_nonzero_chunk_from(a, b, idx_of_chunk),
+            ZeroWhen::Right => Without.combine_next_nonzero_chunk_from(b, a, idx_of_chunk),
+        }
+    }
+}
+
+impl<F: Fn(Chunk, Chunk, Chunk) -> Chunk + Copy> FnOp3<F> {
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+impl<F: Fn(Chunk, Chunk, Chunk) -> Chunk + Copy> TernChunkOp for FnOp3<F> {
+    fn combine_chunks3(
+        self,
+        a: Option<Chunk>,
+        b: Option<Chunk>,
+        c: Option<Chunk>,
+    ) -> Option<Chunk> {
+        if a.is_none() && b.is_none() && c.is_none() {
+            None
+        } else {
+            Some((self.f)(z(a), z(b), z(c)))
+        }
+    }
+    fn combine_zero_chunks_from_conservative3(
+        self,
+        ncfa: usize,
+        ncfb: usize,
+        ncfc: usize,
+    ) -> usize {
+        ncfa.max(ncfb).max(ncfc)
+    }
+}
@@ -0,0 +1,89 @@
+use super::bin_ops::{And, Or, Xor};
+use crate::{Chunk, ChunkRead, ManyChunkOp};
+
+/// Index of the first chunk at or after idx_of_chunk that is nonzero in any of the readers.
+fn next_nonzero_chunk_in_any<A: ChunkRead + ?Sized>(
+    readers: &[&A],
+    idx_of_chunk: usize,
+) -> Option<usize> {
+    readers.iter().filter_map(|r| r.next_nonzero_chunk_from(idx_of_chunk)).map(|(idx, _)| idx).min()
+}
+
+impl ManyChunkOp for Or {
+    fn combine_many_chunks<I: Iterator<Item = Option<Chunk>>>(self, chunks: I) -> Option<Chunk> {
+        chunks.fold(None, |acc, chunk| match (acc, chunk) {
+            (None, None) => None,
+            (acc, chunk) => Some(acc.unwrap_or(0) | chunk.unwrap_or(0)),
+        })
+    }
+    fn combine_many_zero_chunks_from_conservative<I: Iterator<Item = usize>>(
+        self,
+        ncfs: I,
+    ) -> usize {
+        ncfs.max().unwrap_or(0)
+    }
+    fn combine_many_next_nonzero_chunk_from<A: ChunkRead + ?Sized>(
+        self,
+        readers: &[&A],
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        next_nonzero_chunk_in_any(readers, idx_of_chunk)
+    }
+}
+impl ManyChunkOp for Xor {
+    fn combine_many_chunks<I: Iterator<Item = Option<Chunk>>>(self, chunks: I) -> Option<Chunk> {
+        chunks.fold(None, |acc, chunk| match (acc, chunk) {
+            (None, None) => None,
+            (acc, chunk) => Some(acc.unwrap_or(0) ^ chunk.unwrap_or(0)),
+        })
+    }
+    fn combine_many_zero_chunks_from_conservative<I: Iterator<Item = usize>>(
+        self,
+        ncfs: I,
+    ) -> usize {
+        ncfs.max().unwrap_or(0)
+    }
+    fn combine_many_next_nonzero_chunk_from<A: ChunkRead + ?Sized>(
+        self,
+        readers: &[&A],
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        next_nonzero_chunk_in_any(readers, idx_of_chunk)
+    }
+}
+impl ManyChunkOp for And {
+    fn combine_many_chunks<I: Iterator<Item = Option<Chunk>>>(self, chunks: I) -> Option<Chunk> {
+        let mut acc = None;
+        for chunk in chunks {
+            // any None means every subsequent chunk is zero. skip the remaining readers
+            acc = Some(acc.unwrap_or(!0) & chunk?);
+        }
+        acc
+    }
+    fn combine_many_zero_chunks_from_conservative<I: Iterator<Item = usize>>(
+        self,
+        ncfs: I,
+    ) -> usize {
+        ncfs.min().unwrap_or(0)
+    }
+    fn combine_many_next_nonzero_chunk_from<A: ChunkRead + ?Sized>(
+        self,
+        readers: &[&A],
+        mut idx_of_chunk: usize,
+    ) -> Option<usize> {
+        if readers.is_empty() {
+            return None;
+        }
+        // leapfrog until all readers have a nonzero chunk at the same index
+        'leapfrog: loop {
+            for r in readers {
+                let (next, _) = r.next_nonzero_chunk_from(idx_of_chunk)?;
+                if idx_of_chunk < next {
+                    idx_of_chunk = next;
+                    continue 'leapfrog;
+                }
+            }
+            return Some(idx_of_chunk);
+        }
+    }
+}
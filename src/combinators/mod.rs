@@ -1,11 +1,13 @@
-use super::{Chunk, ChunkRead};
+use super::{Chunk, ChunkBitAddr, ChunkRead, Index};
 
 pub mod bin_ops;
+pub mod fn_ops;
+pub mod many_ops;
+pub mod tern_ops;
+pub mod un_ops;
 
 ///////////////////////////////
 
-// pub struct NotChunks<'a, A: ChunkRead + ?Sized>(pub &'a A);
-
 pub struct EmptyIndexSet;
 
 impl ChunkRead for EmptyIndexSet {
@@ -21,7 +23,18 @@ pub trait BinChunkOp: Copy {
     /// NONE means zero chunk AND all subsequent chunks are zero
     fn combine_chunks(self, a: Option<Chunk>, b: Option<Chunk>) -> Option<Chunk>;
     fn combine_zero_chunks_from_conservative(self, ncfa: usize, ncfb: usize) -> usize;
-    fn combine_readers<'a, A: ChunkRead, B: ChunkRead>(
+    /// Returns some idx >= idx_of_chunk s.t. all combined chunks in idx_of_chunk..idx are zero,
+    /// or None if all combined chunks from idx_of_chunk onwards are zero.
+    /// The combined chunk at idx itself may still be zero. By default, nothing is skipped.
+    fn combine_next_nonzero_chunk_from<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
+        self,
+        _a: &A,
+        _b: &B,
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        Some(idx_of_chunk)
+    }
+    fn combine_readers<'a, A: ChunkRead + ?Sized, B: ChunkRead + ?Sized>(
         self,
         a: &'a A,
         b: &'a B,
@@ -30,14 +43,309 @@ pub trait BinChunkOp: Copy {
     }
 }
 
+/// Combines the chunks of any number of readers, e.g., bin_ops::Or for their union.
+pub trait ManyChunkOp: Copy {
+    /// Given the chunks of all readers at some index, in order. NONE as in BinChunkOp::combine_chunks.
+    fn combine_many_chunks<I: Iterator<Item = Option<Chunk>>>(self, chunks: I) -> Option<Chunk>;
+    fn combine_many_zero_chunks_from_conservative<I: Iterator<Item = usize>>(
+        self,
+        ncfs: I,
+    ) -> usize;
+    /// As BinChunkOp::combine_next_nonzero_chunk_from. By default, nothing is skipped.
+    fn combine_many_next_nonzero_chunk_from<A: ChunkRead + ?Sized>(
+        self,
+        _readers: &[&A],
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        Some(idx_of_chunk)
+    }
+}
+
+/// Combines the chunks of three readers, e.g., tern_ops::Mux to select chunks from b or c.
+pub trait TernChunkOp: Copy {
+    /// NONE as in BinChunkOp::combine_chunks.
+    fn combine_chunks3(self, a: Option<Chunk>, b: Option<Chunk>, c: Option<Chunk>)
+        -> Option<Chunk>;
+    fn combine_zero_chunks_from_conservative3(self, ncfa: usize, ncfb: usize, ncfc: usize)
+        -> usize;
+    /// As BinChunkOp::combine_next_nonzero_chunk_from. By default, nothing is skipped.
+    fn combine_next_nonzero_chunk_from3<A, B, C>(
+        self,
+        _a: &A,
+        _b: &B,
+        _c: &C,
+        idx_of_chunk: usize,
+    ) -> Option<usize>
+    where
+        A: ChunkRead + ?Sized,
+        B: ChunkRead + ?Sized,
+        C: ChunkRead + ?Sized,
+    {
+        Some(idx_of_chunk)
+    }
+    fn combine_readers3<'a, A, B, C>(
+        self,
+        a: &'a A,
+        b: &'a B,
+        c: &'a C,
+    ) -> TernCombinedChunkReads<'a, A, B, C, Self>
+    where
+        A: ChunkRead + ?Sized,
+        B: ChunkRead + ?Sized,
+        C: ChunkRead + ?Sized,
+    {
+        TernCombinedChunkReads { a, b, c, op: self }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TernCombinedChunkReads<'a, A, B, C, O>
+where
+    A: ChunkRead + ?Sized,
+    B: ChunkRead + ?Sized,
+    C: ChunkRead + ?Sized,
+    O: TernChunkOp,
+{
+    pub a: &'a A,
+    pub b: &'a B,
+    pub c: &'a C,
+    pub op: O,
+}
+
+/// Transforms the chunks of a single reader, e.g., un_ops::Not for its complement.
+pub trait UnChunkOp: Copy {
+    /// Given the reader's chunk at idx_of_chunk. NONE as in BinChunkOp::combine_chunks.
+    fn map_chunk(self, idx_of_chunk: usize, a: Option<Chunk>) -> Option<Chunk>;
+    fn map_zero_chunks_from_conservative(self, ncfa: usize) -> usize;
+    /// As BinChunkOp::combine_next_nonzero_chunk_from. By default, nothing is skipped.
+    fn map_next_nonzero_chunk_from<A: ChunkRead + ?Sized>(
+        self,
+        _a: &A,
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        Some(idx_of_chunk)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MappedChunkReads<'a, A: ChunkRead + ?Sized, O: UnChunkOp> {
+    pub a: &'a A,
+    pub op: O,
+}
+
+/// The indices in 0..bound which are not in inner.
+/// Unlike inner, this view has no indices at or beyond bound.
+#[derive(Debug, Copy, Clone)]
+pub struct Complement<'a, A: ChunkRead + ?Sized> {
+    pub inner: &'a A,
+    pub bound: Index,
+}
+
+/// The indices of a, each increased by `by`.
+#[derive(Debug, Copy, Clone)]
+pub struct ShiftedUp<'a, A: ChunkRead + ?Sized> {
+    pub a: &'a A,
+    pub by: Index,
+}
+
+/// The indices of a, each decreased by `by`, without those less than `by`.
+#[derive(Debug, Copy, Clone)]
+pub struct ShiftedDown<'a, A: ChunkRead + ?Sized> {
+    pub a: &'a A,
+    pub by: Index,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct CombinedChunkReads<'a, A: ChunkRead + ?Sized, B: ChunkRead + ?Sized, O: BinChunkOp> {
     pub a: &'a A,
     pub b: &'a B,
     pub op: O,
 }
+
+/// Combines the chunks of any number of readers of the same type with op in a single pass.
+/// For readers of different types, use `A = dyn DynChunkRead`.
+#[derive(Debug, Clone)]
+pub struct ManyCombinedChunkReads<'a, A: ChunkRead + ?Sized, O: ManyChunkOp> {
+    pub readers: Vec<&'a A>,
+    pub op: O,
+}
+/// Union of many readers.
+pub type UnionAll<'a, A> = ManyCombinedChunkReads<'a, A, bin_ops::Or>;
+/// Intersection of many readers. The intersection of no readers is empty.
+pub type IntersectAll<'a, A> = ManyCombinedChunkReads<'a, A, bin_ops::And>;
+/// Symmetric difference of many readers, i.e., the indices in an odd number of them.
+pub type XorAll<'a, A> = ManyCombinedChunkReads<'a, A, bin_ops::Xor>;
 ///////////////////////////////
 
+impl<'a, A: ChunkRead + ?Sized, O: ManyChunkOp + Default> ManyCombinedChunkReads<'a, A, O> {
+    pub fn new<I: IntoIterator<Item = &'a A>>(readers: I) -> Self {
+        Self { readers: readers.into_iter().collect(), op: O::default() }
+    }
+}
+
+impl<A, B, C, O> ChunkRead for TernCombinedChunkReads<'_, A, B, C, O>
+where
+    A: ChunkRead + ?Sized,
+    B: ChunkRead + ?Sized,
+    C: ChunkRead + ?Sized,
+    O: TernChunkOp,
+{
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.op.combine_chunks3(
+            self.a.get_chunk(idx_of_chunk),
+            self.b.get_chunk(idx_of_chunk),
+            self.c.get_chunk(idx_of_chunk),
+        )
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        self.op.combine_zero_chunks_from_conservative3(
+            self.a.zero_chunks_from_conservative(),
+            self.b.zero_chunks_from_conservative(),
+            self.c.zero_chunks_from_conservative(),
+        )
+    }
+    fn next_nonzero_chunk_from(&self, mut idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        loop {
+            idx_of_chunk =
+                self.op.combine_next_nonzero_chunk_from3(self.a, self.b, self.c, idx_of_chunk)?;
+            let chunk = self.get_chunk(idx_of_chunk)?;
+            if chunk != 0 {
+                return Some((idx_of_chunk, chunk));
+            }
+            idx_of_chunk += 1;
+        }
+    }
+}
+
+impl<A: ChunkRead + ?Sized, O: UnChunkOp> ChunkRead for MappedChunkReads<'_, A, O> {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.op.map_chunk(idx_of_chunk, self.a.get_chunk(idx_of_chunk))
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        self.op.map_zero_chunks_from_conservative(self.a.zero_chunks_from_conservative())
+    }
+    fn next_nonzero_chunk_from(&self, mut idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        loop {
+            idx_of_chunk = self.op.map_next_nonzero_chunk_from(self.a, idx_of_chunk)?;
+            let chunk = self.get_chunk(idx_of_chunk)?;
+            if chunk != 0 {
+                return Some((idx_of_chunk, chunk));
+            }
+            idx_of_chunk += 1;
+        }
+    }
+}
+
+impl<A: ChunkRead + ?Sized> Complement<'_, A> {
+    fn op(&self) -> un_ops::Not {
+        un_ops::Not { bound: self.bound }
+    }
+}
+impl<A: ChunkRead + ?Sized> ChunkRead for Complement<'_, A> {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.op().map_chunk(idx_of_chunk, self.inner.get_chunk(idx_of_chunk))
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        self.op().map_zero_chunks_from_conservative(self.inner.zero_chunks_from_conservative())
+    }
+}
+
+impl<A: ChunkRead + ?Sized> ChunkRead for ShiftedUp<'_, A> {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        let by = ChunkBitAddr::from_bit_idx(self.by);
+        let Some(idx_of_src) = idx_of_chunk.checked_sub(by.idx_of_chunk) else {
+            return Some(0);
+        };
+        if by.idx_in_chunk == 0 {
+            return self.a.get_chunk(idx_of_src);
+        }
+        // the chunk combines the high bits of the src chunk before with the low bits of the src chunk
+        let below = match idx_of_src.checked_sub(1) {
+            Some(idx_of_below) => self.a.get_chunk(idx_of_below)?,
+            None => 0,
+        };
+        let src = self.a.get_chunk(idx_of_src).unwrap_or(0);
+        Some(src << by.idx_in_chunk | below >> (usize::BITS - by.idx_in_chunk))
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        let by = ChunkBitAddr::from_bit_idx(self.by);
+        let spill = (by.idx_in_chunk > 0) as usize;
+        self.a.zero_chunks_from_conservative().saturating_add(by.idx_of_chunk + spill)
+    }
+    fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        let by = ChunkBitAddr::from_bit_idx(self.by);
+        let spill = (by.idx_in_chunk > 0) as usize;
+        let mut idx_of_chunk = idx_of_chunk.max(by.idx_of_chunk);
+        loop {
+            // the chunk at idx_of_chunk is nonzero only if the src chunk, or the one before, is nonzero
+            let idx_of_src = idx_of_chunk - by.idx_of_chunk;
+            let (idx_of_nonzero_src, _) =
+                self.a.next_nonzero_chunk_from(idx_of_src.saturating_sub(spill))?;
+            idx_of_chunk = idx_of_chunk.max(idx_of_nonzero_src.checked_add(by.idx_of_chunk)?);
+            let chunk = self.get_chunk(idx_of_chunk)?;
+            if chunk != 0 {
+                return Some((idx_of_chunk, chunk));
+            }
+            idx_of_chunk += 1;
+        }
+    }
+}
+
+impl<A: ChunkRead + ?Sized> ChunkRead for ShiftedDown<'_, A> {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        let by = ChunkBitAddr::from_bit_idx(self.by);
+        let idx_of_src = idx_of_chunk.checked_add(by.idx_of_chunk)?;
+        let src = self.a.get_chunk(idx_of_src)?;
+        if by.idx_in_chunk == 0 {
+            return Some(src);
+        }
+        // the chunk combines the high bits of the src chunk with the low bits of the src chunk after
+        let above = idx_of_src.checked_add(1).and_then(|idx| self.a.get_chunk(idx)).unwrap_or(0);
+        Some(src >> by.idx_in_chunk | above << (usize::BITS - by.idx_in_chunk))
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        let by = ChunkBitAddr::from_bit_idx(self.by);
+        self.a.zero_chunks_from_conservative().saturating_sub(by.idx_of_chunk)
+    }
+    fn next_nonzero_chunk_from(&self, mut idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        let by = ChunkBitAddr::from_bit_idx(self.by);
+        let spill = (by.idx_in_chunk > 0) as usize;
+        loop {
+            // the chunk at idx_of_chunk is nonzero only if the src chunk, or the one after, is nonzero
+            let idx_of_src = idx_of_chunk.checked_add(by.idx_of_chunk)?;
+            let (idx_of_nonzero_src, _) = self.a.next_nonzero_chunk_from(idx_of_src)?;
+            let idx_of_nonzero_src = idx_of_nonzero_src.saturating_sub(spill).max(idx_of_src);
+            idx_of_chunk = idx_of_nonzero_src - by.idx_of_chunk;
+            let chunk = self.get_chunk(idx_of_chunk)?;
+            if chunk != 0 {
+                return Some((idx_of_chunk, chunk));
+            }
+            idx_of_chunk += 1;
+        }
+    }
+}
+
+impl<A: ChunkRead + ?Sized, O: ManyChunkOp> ChunkRead for ManyCombinedChunkReads<'_, A, O> {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.op.combine_many_chunks(self.readers.iter().map(|r| r.get_chunk(idx_of_chunk)))
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        let ncfs = self.readers.iter().map(|r| r.zero_chunks_from_conservative());
+        self.op.combine_many_zero_chunks_from_conservative(ncfs)
+    }
+    fn next_nonzero_chunk_from(&self, mut idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        loop {
+            idx_of_chunk =
+                self.op.combine_many_next_nonzero_chunk_from(&self.readers, idx_of_chunk)?;
+            let chunk = self.get_chunk(idx_of_chunk)?;
+            if chunk != 0 {
+                return Some((idx_of_chunk, chunk));
+            }
+            idx_of_chunk += 1;
+        }
+    }
+}
+
 impl<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized, O: BinChunkOp> ChunkRead
     for CombinedChunkReads<'_, A, B, O>
 {
@@ -50,4 +358,14 @@ impl<A: ChunkRead + ?Sized, B: ChunkRead + ?Sized, O: BinChunkOp> ChunkRead
             self.b.zero_chunks_from_conservative(),
         )
     }
+    fn next_nonzero_chunk_from(&self, mut idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        loop {
+            idx_of_chunk = self.op.combine_next_nonzero_chunk_from(self.a, self.b, idx_of_chunk)?;
+            let chunk = self.get_chunk(idx_of_chunk)?;
+            if chunk != 0 {
+                return Some((idx_of_chunk, chunk));
+            }
+            idx_of_chunk += 1;
+        }
+    }
 }
@@ -0,0 +1,77 @@
+use super::bin_ops::next_nonzero_chunk_in_either;
+use crate::{Chunk, ChunkRead, TernChunkOp};
+
+/// Indices in at least two of the three readers.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Majority;
+/// Indices of b where a has the index, and indices of c where a does not, i.e., `a ? b : c`.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Mux;
+
+//////////
+
+#[inline]
+fn z(chunk: Option<Chunk>) -> Chunk {
+    chunk.unwrap_or(0)
+}
+
+impl TernChunkOp for Majority {
+    fn combine_chunks3(
+        self,
+        a: Option<Chunk>,
+        b: Option<Chunk>,
+        c: Option<Chunk>,
+    ) -> Option<Chunk> {
+        if [a, b, c].iter().filter(|chunk| chunk.is_none()).count() >= 2 {
+            None
+        } else {
+            let [a, b, c] = [z(a), z(b), z(c)];
+            Some((a & b) | (a & c) | (b & c))
+        }
+    }
+    fn combine_zero_chunks_from_conservative3(
+        self,
+        ncfa: usize,
+        ncfb: usize,
+        ncfc: usize,
+    ) -> usize {
+        // the median
+        ncfa.min(ncfb).max(ncfa.max(ncfb).min(ncfc))
+    }
+}
+impl TernChunkOp for Mux {
+    fn combine_chunks3(
+        self,
+        a: Option<Chunk>,
+        b: Option<Chunk>,
+        c: Option<Chunk>,
+    ) -> Option<Chunk> {
+        if b.is_none() && c.is_none() {
+            None
+        } else {
+            Some((z(a) & z(b)) | (!z(a) & z(c)))
+        }
+    }
+    fn combine_zero_chunks_from_conservative3(
+        self,
+        _ncfa: usize,
+        ncfb: usize,
+        ncfc: usize,
+    ) -> usize {
+        ncfb.max(ncfc)
+    }
+    fn combine_next_nonzero_chunk_from3<A, B, C>(
+        self,
+        _a: &A,
+        b: &B,
+        c: &C,
+        idx_of_chunk: usize,
+    ) -> Option<usize>
+    where
+        A: ChunkRead + ?Sized,
+        B: ChunkRead + ?Sized,
+        C: ChunkRead + ?Sized,
+    {
+        next_nonzero_chunk_in_either(b, c, idx_of_chunk)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{index_count_to_chunk_count, Chunk, ChunkBitAddr, ChunkRead, Index, UnChunkOp};
+
+/// Complement within 0..bound.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Not {
+    pub bound: Index,
+}
+/// Drops the indices at or beyond bound.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Truncate {
+    pub bound: Index,
+}
+
+//////////
+
+/// Mask of the bits in the chunk at idx_of_chunk which represent indices in 0..bound,
+/// or None if there are no such bits in this or any subsequent chunk.
+fn chunk_mask_below(bound: Index, idx_of_chunk: usize) -> Option<Chunk> {
+    let cba = ChunkBitAddr::from_bit_idx(bound);
+    match idx_of_chunk.cmp(&cba.idx_of_chunk) {
+        core::cmp::Ordering::Less => Some(!0),
+        core::cmp::Ordering::Equal if cba.idx_in_chunk > 0 => Some(!cba.chunk_mask_from()),
+        _ => None,
+    }
+}
+
+impl UnChunkOp for Not {
+    fn map_chunk(self, idx_of_chunk: usize, a: Option<Chunk>) -> Option<Chunk> {
+        Some(!a.unwrap_or(0) & chunk_mask_below(self.bound, idx_of_chunk)?)
+    }
+    fn map_zero_chunks_from_conservative(self, _ncfa: usize) -> usize {
+        index_count_to_chunk_count(self.bound)
+    }
+}
+impl UnChunkOp for Truncate {
+    fn map_chunk(self, idx_of_chunk: usize, a: Option<Chunk>) -> Option<Chunk> {
+        Some(a? & chunk_mask_below(self.bound, idx_of_chunk)?)
+    }
+    fn map_zero_chunks_from_conservative(self, ncfa: usize) -> usize {
+        ncfa.min(index_count_to_chunk_count(self.bound))
+    }
+    fn map_next_nonzero_chunk_from<A: ChunkRead + ?Sized>(
+        self,
+        a: &A,
+        idx_of_chunk: usize,
+    ) -> Option<usize> {
+        a.next_nonzero_chunk_from(idx_of_chunk).map(|(idx, _)| idx)
+    }
+}
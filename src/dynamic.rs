@@ -0,0 +1,125 @@
+use super::{Chunk, ChunkRead, Index};
+
+/// Object-safe counterpart of ChunkRead, s.t. readers of different types can be used as trait objects,
+/// e.g., in a `Vec<Box<dyn DynChunkRead>>`. Every (sized) ChunkRead implements DynChunkRead,
+/// and `dyn DynChunkRead` implements ChunkRead, providing all its methods.
+/// Methods which ChunkRead implementors commonly override are forwarded to the underlying reader.
+pub trait DynChunkRead {
+    fn dyn_get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk>;
+    fn dyn_zero_chunks_from_conservative(&self) -> usize;
+    fn dyn_next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)>;
+    fn dyn_len(&self) -> usize;
+    fn dyn_is_empty(&self) -> bool;
+    fn dyn_contains(&self, bit_idx: Index) -> bool;
+    fn dyn_min_element(&self) -> Option<Index>;
+    fn dyn_max_element(&self) -> Option<Index>;
+    fn dyn_rank(&self, bit_idx: Index) -> usize;
+    fn dyn_select(&self, k: usize) -> Option<Index>;
+}
+
+///////////////////////////////////////////////////////////////////////
+
+impl<A: ChunkRead> DynChunkRead for A {
+    fn dyn_get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.get_chunk(idx_of_chunk)
+    }
+    fn dyn_zero_chunks_from_conservative(&self) -> usize {
+        self.zero_chunks_from_conservative()
+    }
+    fn dyn_next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        self.next_nonzero_chunk_from(idx_of_chunk)
+    }
+    fn dyn_len(&self) -> usize {
+        self.len()
+    }
+    fn dyn_is_empty(&self) -> bool {
+        self.is_empty()
+    }
+    fn dyn_contains(&self, bit_idx: Index) -> bool {
+        self.contains(bit_idx)
+    }
+    fn dyn_min_element(&self) -> Option<Index> {
+        self.min_element()
+    }
+    fn dyn_max_element(&self) -> Option<Index> {
+        self.max_element()
+    }
+    fn dyn_rank(&self, bit_idx: Index) -> usize {
+        self.rank(bit_idx)
+    }
+    fn dyn_select(&self, k: usize) -> Option<Index> {
+        self.select(k)
+    }
+}
+
+impl ChunkRead for dyn DynChunkRead + '_ {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.dyn_get_chunk(idx_of_chunk)
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        self.dyn_zero_chunks_from_conservative()
+    }
+    fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        self.dyn_next_nonzero_chunk_from(idx_of_chunk)
+    }
+    fn len(&self) -> usize {
+        self.dyn_len()
+    }
+    fn is_empty(&self) -> bool {
+        self.dyn_is_empty()
+    }
+    fn contains(&self, bit_idx: Index) -> bool {
+        self.dyn_contains(bit_idx)
+    }
+    fn min_element(&self) -> Option<Index> {
+        self.dyn_min_element()
+    }
+    fn max_element(&self) -> Option<Index> {
+        self.dyn_max_element()
+    }
+    fn rank(&self, bit_idx: Index) -> usize {
+        self.dyn_rank(bit_idx)
+    }
+    fn select(&self, k: usize) -> Option<Index> {
+        self.dyn_select(k)
+    }
+}
+
+/// Implements ChunkRead for a pointer type by forwarding to its pointee.
+macro_rules! impl_chunk_read_for_pointer {
+    ($($ptr:ty),*) => {$(
+        impl<A: ChunkRead + ?Sized> ChunkRead for $ptr {
+            fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+                (**self).get_chunk(idx_of_chunk)
+            }
+            fn zero_chunks_from_conservative(&self) -> usize {
+                (**self).zero_chunks_from_conservative()
+            }
+            fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+                (**self).next_nonzero_chunk_from(idx_of_chunk)
+            }
+            fn len(&self) -> usize {
+                (**self).len()
+            }
+            fn is_empty(&self) -> bool {
+                (**self).is_empty()
+            }
+            fn contains(&self, bit_idx: Index) -> bool {
+                (**self).contains(bit_idx)
+            }
+            fn min_element(&self) -> Option<Index> {
+                (**self).min_element()
+            }
+            fn max_element(&self) -> Option<Index> {
+                (**self).max_element()
+            }
+            fn rank(&self, bit_idx: Index) -> usize {
+                (**self).rank(bit_idx)
+            }
+            fn select(&self, k: usize) -> Option<Index> {
+                (**self).select(k)
+            }
+        }
+    )*};
+}
+impl_chunk_read_for_pointer!(&A, Box<A>);
@@ -0,0 +1,172 @@
+//! Portable binary encoding of index sets, identical on every platform, regardless of usize::BITS or endianness.
+//!
+//! | bytes | field                                                                      |
+//! |-------|----------------------------------------------------------------------------|
+//! | 4     | MAGIC, i.e., `b"CHIS"`                                                     |
+//! | 1     | VERSION, i.e., 1                                                           |
+//! | 1     | flags: bit 0 is set iff a checksum follows the words. Other bits are zero. |
+//! | 8     | word count W, as a little-endian u64                                       |
+//! | 8 * W | words, as little-endian u64s. Bit j of word i represents index 64 * i + j. |
+//! | 0 / 8 | checksum: FNV-1a (64 bit) of all preceding bytes, as a little-endian u64   |
+//!
+//! The encoding is trimmed: if W > 0, the last word is nonzero. So every set has exactly one encoding
+//! (with and without checksum), and the encoded size depends only on the largest element.
+use super::{u64_word, u64_word_count, ChunkRead, CHUNKS_PER_U64};
+use std::io::{self, Read, Write};
+
+pub const MAGIC: [u8; 4] = *b"CHIS";
+pub const VERSION: u8 = 1;
+const FLAG_CHECKSUM: u8 = 1;
+
+/// Reasons why reading an encoded set may fail.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The reader failed for reasons other than running out of bytes.
+    Io(io::Error),
+    /// The reader ran out of bytes before the end of the encoding.
+    Truncated,
+    /// The first four bytes were not MAGIC.
+    BadMagic([u8; 4]),
+    /// The version is not one this library can read.
+    UnsupportedVersion(u8),
+    /// Flags not defined by this version were set.
+    UnknownFlags(u8),
+    /// The encoding has more words than can be represented with this platform's usize.
+    TooLarge { word_count: u64 },
+    /// The last word is zero, so the encoding is not trimmed.
+    NotTrimmed,
+    /// The stored checksum does not match the checksum computed from the preceding bytes.
+    ChecksumMismatch { stored: u64, computed: u64 },
+}
+
+/// Incremental FNV-1a (64 bit) hash
+struct Fnv1a(u64);
+
+/// Wraps a reader or writer, hashing all bytes that pass through.
+struct Hashing<T> {
+    inner: T,
+    hash: Fnv1a,
+}
+
+///////////////////////////////////////////////////////////////////////
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::Io(e) => write!(f, "i/o error while decoding index set: {}", e),
+            DecodeError::Truncated => write!(f, "index set encoding is truncated"),
+            DecodeError::BadMagic(magic) => write!(f, "bad magic bytes {:?}", magic),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported encoding version {}", v),
+            DecodeError::UnknownFlags(flags) => write!(f, "unknown encoding flags {:#010b}", flags),
+            DecodeError::TooLarge { word_count } => {
+                write!(f, "{} words are too many for this platform", word_count)
+            }
+            DecodeError::NotTrimmed => write!(f, "index set encoding has a trailing zero word"),
+            DecodeError::ChecksumMismatch { stored, computed } => {
+                write!(f, "checksum mismatch: stored {:#x}, computed {:#x}", stored, computed)
+            }
+        }
+    }
+}
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => DecodeError::Truncated,
+            _ => DecodeError::Io(e),
+        }
+    }
+}
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+impl<W: Write> Hashing<W> {
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hash.update(bytes);
+        self.inner.write_all(bytes)
+    }
+}
+impl<R: Read> Hashing<R> {
+    fn read_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.inner.read_exact(&mut bytes)?;
+        self.hash.update(&bytes);
+        Ok(bytes)
+    }
+}
+
+/// Writes the encoding of a's elements to writer.
+pub(crate) fn write_chunks<A: ChunkRead + ?Sized, W: Write>(
+    a: &A,
+    writer: &mut W,
+    checksum: bool,
+) -> io::Result<()> {
+    let mut writer = Hashing { inner: writer, hash: Fnv1a::new() };
+    let flags = if checksum { FLAG_CHECKSUM } else { 0 };
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&[VERSION, flags])?;
+    let word_count = u64_word_count(a);
+    writer.write_all(&(word_count as u64).to_le_bytes())?;
+    for idx_of_word in 0..word_count {
+        writer.write_all(&u64_word(a, idx_of_word).to_le_bytes())?;
+    }
+    if checksum {
+        let computed = writer.hash.0;
+        writer.write_all(&computed.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Reads an encoding from reader, returning its words.
+pub(crate) fn read_words<R: Read>(reader: &mut R) -> Result<Vec<u64>, DecodeError> {
+    let mut reader = Hashing { inner: reader, hash: Fnv1a::new() };
+    let magic = reader.read_array::<4>()?;
+    if magic != MAGIC {
+        return Err(DecodeError::BadMagic(magic));
+    }
+    let [version, flags] = reader.read_array::<2>()?;
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    if flags & !FLAG_CHECKSUM != 0 {
+        return Err(DecodeError::UnknownFlags(flags));
+    }
+    let word_count = u64::from_le_bytes(reader.read_array()?);
+    // every chunk must be addressable by some bit index
+    let max_word_count = (usize::MAX / usize::BITS as usize / CHUNKS_PER_U64) as u64;
+    if max_word_count < word_count {
+        return Err(DecodeError::TooLarge { word_count });
+    }
+    // grows as words are actually read, so a corrupt word count cannot cause a huge allocation
+    let mut words = Vec::with_capacity((word_count as usize).min(1 << 12));
+    for _ in 0..word_count {
+        words.push(u64::from_le_bytes(reader.read_array()?));
+    }
+    if words.last() == Some(&0) {
+        return Err(DecodeError::NotTrimmed);
+    }
+    if flags & FLAG_CHECKSUM != 0 {
+        let computed = reader.hash.0;
+        let stored = u64::from_le_bytes(reader.read_array()?);
+        if stored != computed {
+            return Err(DecodeError::ChecksumMismatch { stored, computed });
+        }
+    }
+    Ok(words)
+}
@@ -1,87 +1,102 @@
-use super::{Chunk, ChunkRead, CHUNK_BYTES};
-use crate::index_count_to_chunk_count;
+use super::{Chunk, ChunkRead};
+use crate::summary::ChunkSummary;
+use crate::word::{
+    chunk_count_of_words, chunk_from_words, first_chunk_of_word, update_chunk_in_words,
+    word_range_of_chunk, Word,
+};
 use crate::BinChunkOp;
-use crate::ChunkBitAddr;
 use crate::Index;
 use core::fmt::Debug;
 use core::iter::FromIterator;
 use core::ops::{Range, RangeTo};
 
-union PackedChunkStorage<const N: usize> {
-    heap_chunks_ptr: *mut Chunk,
-    stack_chunks: [Chunk; N],
+union PackedChunkStorage<const N: usize, W: Word> {
+    heap_chunks_ptr: *mut W,
+    stack_chunks: [W; N],
 }
-impl<const N: usize> Debug for IndexSet<N> {
+impl<const N: usize, W: Word> Debug for IndexSet<N, W> {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_set().entries(self.iter()).finish()
     }
 }
-impl<const N: usize> PartialEq for IndexSet<N> {
+impl<const N: usize, W: Word> PartialEq for IndexSet<N, W> {
     fn eq(&self, other: &Self) -> bool {
         self.set_cmp(other) == Some(core::cmp::Ordering::Equal)
     }
 }
-impl<const N: usize> Eq for IndexSet<N> {}
-impl<const N: usize> core::hash::Hash for IndexSet<N> {
+impl<const N: usize, W: Word> Eq for IndexSet<N, W> {}
+impl<const N: usize, W: Word> core::hash::Hash for IndexSet<N, W> {
     fn hash<H: core::hash::Hasher>(&self, hasher: &mut H) {
-        self.as_chunks().hash(hasher)
+        // equal sets may differ in their trailing zero chunks
+        self.as_words()[..self.trimmed_word_count()].hash(hasher)
     }
 }
 
-// pub struct IndexSubsetIter<const N: usize> {
-//     // already_returned_buffered is initially FALSE. then becomes and stays true
-//     buffered: IndexSet<N>,
-//     already_returned_buffered: bool,
-// }
-
 /// Stores a set of indices in a contiguous array of bits packed into Chunks.
-/// If chunk_capacity() <= N, the array is kept on the stack, otherwise on the heap.
+/// If word_capacity() <= N, the array is kept on the stack, otherwise on the heap.
 ///  
 /// Stores an array of Chunks of the heap, each storing usize::BITS contiguous indices
 /// IndexSets with <=N chunks store their data on the heap, otherwise they store it on the stack.
 ///
-
-pub struct IndexSet<const N: usize> {
+/// The array is made of words of type W, each storing W::BITS contiguous indices. W is Chunk by default.
+/// Methods measuring the storage (as_words, word_capacity, etc.) count words of type W,
+/// while ChunkRead methods (get_chunk etc.) count Chunks, whatever W is.
+/// The chunk-named variants of the former (as_chunks, chunk_capacity, etc.) exist only for W = Chunk,
+/// for which the two coincide.
+///
+/// Optionally (see enable_summary), also maintains a summary of which chunks are nonzero,
+/// s.t. iteration and queries skip over long runs of zero chunks.
+pub struct IndexSet<const N: usize, W: Word = Chunk> {
     // invariants:
     // N <= self.chunk_count
     // if self.chunk_count == N: using stack_chunks
-    // if N < self.chunk_count : using heap_chunks_ptr; points to [usize; self.chunk_count] on heap
-    packed_chunk_storage: PackedChunkStorage<N>,
+    // if N < self.chunk_count : using heap_chunks_ptr; points to [W; self.chunk_count] on heap
+    // if summary is Some and not stale: it summarizes exactly as_words()
+    packed_chunk_storage: PackedChunkStorage<N, W>,
     chunk_count: usize,
+    summary: Option<Box<ChunkSummary>>,
 }
-impl<const N: usize> Default for IndexSet<N> {
+impl<const N: usize, W: Word> Default for IndexSet<N, W> {
     fn default() -> Self {
-        Self::with_chunk_capacity(0)
+        Self::with_word_capacity(0)
     }
 }
-impl<const N: usize> Clone for IndexSet<N> {
+impl<const N: usize, W: Word> Clone for IndexSet<N, W> {
     fn clone(&self) -> Self {
-        let mut new = Self::with_chunk_capacity(self.zero_chunks_from_exact());
-        for (src, dest) in self.as_chunks().iter().zip(new.as_chunks_mut().iter_mut()) {
+        let mut new = Self::with_word_capacity(self.trimmed_word_count());
+        for (src, dest) in self.as_words().iter().zip(new.as_words_mut().iter_mut()) {
             *dest = *src;
         }
+        if self.has_summary() {
+            new.enable_summary();
+        }
         new
     }
 }
-impl<const N: usize> IndexSet<N> {
+/// Returns the index of the chunk of type W storing bit_idx, and the mask of bit_idx in that chunk.
+fn chunk_addr<W: Word>(bit_idx: Index) -> (usize, W) {
+    let bits = W::BITS as usize;
+    (bit_idx / bits, W::ONE << (bit_idx % bits) as u32)
+}
+impl<const N: usize, W: Word> IndexSet<N, W> {
     pub fn insert_all_in_range(&mut self, mut range: Range<usize>) {
         range.start = range.start.min(range.end);
         if range.is_empty() {
             return;
         }
-        const B: usize = usize::BITS as usize;
-        let first_chunk: Chunk = (!0) << range.start % B;
-        let first_chunk_at = range.start as usize / B;
-        let mut last_chunk: Chunk = !((!0) << range.end % B);
-        let mut last_chunk_at = range.end as usize / B;
-        if last_chunk == 0 {
+        let b = W::BITS as usize;
+        let first_chunk: W = W::MAX << (range.start % b) as u32;
+        let first_chunk_at = range.start / b;
+        let mut last_chunk: W = !(W::MAX << (range.end % b) as u32);
+        let mut last_chunk_at = range.end / b;
+        if last_chunk == W::ZERO {
             last_chunk_at -= 1;
-            last_chunk = !0;
+            last_chunk = W::MAX;
         }
         if self.chunk_count <= last_chunk_at {
-            self.resize_chunks_to_accomodate(last_chunk_at);
+            self.resize_words_to_accomodate(last_chunk_at);
         }
-        let chunks = self.as_chunks_mut();
+        let chunks = self.raw_words_mut();
         if first_chunk_at == last_chunk_at {
             let only_chunk = first_chunk & last_chunk;
             chunks[first_chunk_at] |= only_chunk;
@@ -89,31 +104,32 @@ impl<const N: usize> IndexSet<N> {
             chunks[first_chunk_at] |= first_chunk;
             if first_chunk_at + 1 < last_chunk_at {
                 for chunk in chunks[(first_chunk_at + 1)..last_chunk_at].iter_mut() {
-                    *chunk = !0;
+                    *chunk = W::MAX;
                 }
             }
             chunks[last_chunk_at] |= last_chunk;
         }
+        self.update_summary(first_chunk_at..last_chunk_at + 1);
     }
     pub fn remove_all_in_range(&mut self, mut range: Range<usize>) {
         range.start = range.start.min(range.end);
         if range.is_empty() {
             return;
         }
-        const B: usize = usize::BITS as usize;
+        let b = W::BITS as usize;
         // these chunks are all negated!
-        let first_chunk: Chunk = (!0) << range.start % B;
-        let first_chunk_at = range.start as usize / B;
-        let chunks = self.as_chunks_mut();
+        let first_chunk: W = W::MAX << (range.start % b) as u32;
+        let first_chunk_at = range.start / b;
+        let chunks = self.raw_words_mut();
         if chunks.len() <= first_chunk_at {
             // nothing to do here!
             return;
         }
-        let mut last_chunk: Chunk = !((!0) << range.end % B);
-        let mut last_chunk_at = range.end as usize / B;
-        if last_chunk == 0 {
+        let mut last_chunk: W = !(W::MAX << (range.end % b) as u32);
+        let mut last_chunk_at = range.end / b;
+        if last_chunk == W::ZERO {
             last_chunk_at -= 1;
-            last_chunk = !0;
+            last_chunk = W::MAX;
         }
         if first_chunk_at == last_chunk_at {
             let only_chunk = first_chunk & last_chunk;
@@ -123,13 +139,15 @@ impl<const N: usize> IndexSet<N> {
             if first_chunk_at + 1 < last_chunk_at {
                 let end = last_chunk_at.min(chunks.len());
                 for chunk in chunks[(first_chunk_at + 1)..end].iter_mut() {
-                    *chunk = 0;
+                    *chunk = W::ZERO;
                 }
             }
             if let Some(chunk) = chunks.get_mut(last_chunk_at) {
                 *chunk &= !last_chunk;
             }
         }
+        let end = (last_chunk_at + 1).min(self.chunk_count);
+        self.update_summary(first_chunk_at..end);
     }
     /// If possible, replaces this set with the previous in the powerset order.
     /// This is an ordering on sets of positive integers as follows: {}, {0}, {1}, {0,1}, {2}, {0,2}, {1,2}, ...
@@ -138,12 +156,13 @@ impl<const N: usize> IndexSet<N> {
             // chunks are [0,0,0, ... ,0]
             false
         } else {
-            let chunks = self.as_chunks_mut();
-            for chunk in chunks.iter_mut() {
-                let (new_chunk, underflowed) = chunk.overflowing_sub(1);
+            let chunks = self.raw_words_mut();
+            for (idx_of_chunk, chunk) in chunks.iter_mut().enumerate() {
+                let (new_chunk, underflowed) = chunk.overflowing_sub(W::ONE);
                 *chunk = new_chunk;
                 if !underflowed {
                     // ok we didn't underflow. done here
+                    self.update_summary(0..idx_of_chunk + 1);
                     return true;
                 }
             }
@@ -151,68 +170,129 @@ impl<const N: usize> IndexSet<N> {
             unreachable!()
         }
     }
+    /// Replaces this set with the next in the powerset order (see try_decrease_in_powerset_order),
+    /// growing the chunk capacity if necessary.
+    /// Returns false, leaving the set unchanged, only if growing the chunk capacity fails.
+    pub fn try_increase_in_powerset_order(&mut self) -> bool {
+        let chunks = self.raw_words_mut();
+        for (idx_of_chunk, chunk) in chunks.iter_mut().enumerate() {
+            let (new_chunk, overflowed) = chunk.overflowing_add(W::ONE);
+            *chunk = new_chunk;
+            if !overflowed {
+                self.update_summary(0..idx_of_chunk + 1);
+                return true;
+            }
+        }
+        // every chunk overflowed to zero. carry into a new chunk
+        let chunk_count = self.chunk_count;
+        let mut grown = match Self::try_with_word_capacity(chunk_count + 1) {
+            Some(grown) => grown,
+            None => {
+                // undo, leaving every chunk at its maximum
+                self.raw_words_mut().iter_mut().for_each(|chunk| *chunk = W::MAX);
+                return false;
+            }
+        };
+        grown.raw_words_mut()[chunk_count] = W::ONE;
+        if self.has_summary() {
+            grown.enable_summary();
+        }
+        *self = grown;
+        true
+    }
+    /// Creates the set at the given position in the powerset order, e.g., as returned by ChunkRead::powerset_rank.
+    /// The rank is a little-endian sequence of chunks, i.e., its bits are the elements.
+    pub fn from_powerset_rank(rank: &[Chunk]) -> Self {
+        Self::from_chunk_read(rank)
+    }
 
     /// Returns the IndexSet's index capacity.
     pub fn capacity(&self) -> RangeTo<usize> {
-        ..(self.chunk_count * usize::BITS as usize)
+        ..(self.chunk_count * W::BITS as usize)
     }
     /// Creates an empty IndexSet with minimal chunk_count s.t. index_count <= capacity().
     pub fn with_min_capacity(index_count: usize) -> Self {
-        Self::with_chunk_capacity(index_count_to_chunk_count(index_count))
+        Self::with_word_capacity(index_count.div_ceil(W::BITS as usize))
     }
-    /// Returns the IndexSet's chunk capacity
-    pub fn chunk_capacity(&self) -> usize {
+    /// Returns the IndexSet's capacity in words of type W, not Chunks.
+    pub fn word_capacity(&self) -> usize {
         self.chunk_count
     }
-    /// Creates an empty IndexSet with the given chunk capacity
-    pub fn with_chunk_capacity(mut chunk_count: usize) -> Self {
+    /// Creates an empty IndexSet with the given capacity in words of type W, not Chunks.
+    pub fn with_word_capacity(word_count: usize) -> Self {
+        Self::try_with_word_capacity(word_count).unwrap_or_else(|| {
+            // heap_layout panics if word_count is too large for any allocation
+            std::alloc::handle_alloc_error(Self::heap_layout(word_count))
+        })
+    }
+    /// Like with_word_capacity, but returns None rather than aborting if the allocation fails.
+    /// For capacities computed from untrusted input, e.g., when deserializing.
+    pub(crate) fn try_with_word_capacity(mut chunk_count: usize) -> Option<Self> {
         chunk_count = chunk_count.max(N);
         let packed_chunk_storage = if chunk_count == N {
             // stack
-            PackedChunkStorage { stack_chunks: [0; N] }
+            PackedChunkStorage { stack_chunks: [W::ZERO; N] }
         } else {
             // N < chunk_count
             // allocate chunk_count chunks on the heap
-            assert!(chunk_count <= usize::MAX / CHUNK_BYTES);
-            let layout = unsafe {
-                // will be valid layout. Cannot be zero. cannot be misaligned. Cannot be too large.
-                std::alloc::Layout::from_size_align_unchecked(
-                    CHUNK_BYTES * chunk_count,
-                    CHUNK_BYTES,
-                )
-            };
+            if isize::MAX as usize / core::mem::size_of::<W>() < chunk_count {
+                return None;
+            }
+            let layout = Self::heap_layout(chunk_count);
             let heap_chunks_ptr = unsafe {
                 // safe! layout.size() != 0
                 std::alloc::alloc_zeroed(layout)
-            } as *mut usize;
+            } as *mut W;
+            if heap_chunks_ptr.is_null() {
+                return None;
+            }
             PackedChunkStorage { heap_chunks_ptr }
         };
-        Self { packed_chunk_storage, chunk_count }
+        Some(Self { packed_chunk_storage, chunk_count, summary: None })
     }
-    /// Creates an IndexSet from a sequence of contiguous chunks.
-    /// Result may have greater capacity than the number of chunks provided.
-    pub fn from_chunk_iter<I: IntoIterator<Item = Chunk>>(it: I) -> Self {
+    /// Layout of the heap allocation storing chunk_count chunks. Requires 0 < chunk_count.
+    fn heap_layout(chunk_count: usize) -> std::alloc::Layout {
+        let chunk_bytes = core::mem::size_of::<W>();
+        assert!(chunk_count <= isize::MAX as usize / chunk_bytes);
+        unsafe {
+            // will be valid layout. Cannot be zero. cannot be misaligned. Cannot be too large.
+            std::alloc::Layout::from_size_align_unchecked(
+                chunk_bytes * chunk_count,
+                core::mem::align_of::<W>(),
+            )
+        }
+    }
+    /// Creates an IndexSet from a sequence of contiguous words of type W.
+    /// Result may have greater capacity than the number of words provided.
+    pub fn from_word_iter<I: IntoIterator<Item = W>>(it: I) -> Self {
         let it = it.into_iter();
-        let mut me = Self::with_chunk_capacity(it.size_hint().0);
+        let mut me = Self::with_word_capacity(it.size_hint().0);
         for (idx_of_chunk, read_chunk) in it.enumerate() {
             if me.chunk_count <= idx_of_chunk {
-                me.resize_chunks_to_accomodate(idx_of_chunk)
+                me.resize_words_to_accomodate(idx_of_chunk)
             }
             let write_chunk = unsafe {
                 // certainly in bounds
-                me.as_chunks_mut().get_unchecked_mut(idx_of_chunk)
+                me.as_words_mut().get_unchecked_mut(idx_of_chunk)
             };
             *write_chunk = read_chunk;
         }
         me
     }
-    /// Creates an IndexSet from a sequence of contiguous chunks.
-    /// Result has a chunk capacity equivalent to the length of the slice.
-    pub fn from_chunk_slice(chunks: &[Chunk]) -> Self {
-        Self::from_chunk_iter(chunks.iter().copied())
+    /// Creates an IndexSet from a sequence of contiguous words of type W.
+    /// Result has a word capacity equivalent to the length of the slice.
+    pub fn from_word_slice(words: &[W]) -> Self {
+        Self::from_word_iter(words.iter().copied())
     }
-    /// Returns an immutable slice of the stored Chunks
-    pub fn as_chunks(&self) -> &[Chunk] {
+    /// Creates an IndexSet with the elements of any ChunkRead.
+    /// Unlike ChunkRead::to_index_set, the result may store chunks of any type W.
+    pub fn from_chunk_read<A: ChunkRead + ?Sized>(a: &A) -> Self {
+        let mut me = Self::default();
+        me.insert_all(a);
+        me
+    }
+    /// Returns an immutable slice of the stored words of type W.
+    pub fn as_words(&self) -> &[W] {
         unsafe {
             let chunks_ptr = if self.chunk_count == N {
                 self.packed_chunk_storage.stack_chunks.as_ptr()
@@ -222,8 +302,16 @@ impl<const N: usize> IndexSet<N> {
             std::slice::from_raw_parts(chunks_ptr, self.chunk_count)
         }
     }
-    /// Returns a mutable slice of the stored Chunks
-    pub fn as_chunks_mut(&mut self) -> &mut [Chunk] {
+    /// Returns a mutable slice of the stored words of type W.
+    /// If this set maintains a summary, it is rebuilt lazily upon the next mutation via another method.
+    pub fn as_words_mut(&mut self) -> &mut [W] {
+        if let Some(summary) = &mut self.summary {
+            summary.stale = true;
+        }
+        self.raw_words_mut()
+    }
+    /// Like as_words_mut, but leaves the summary as is. Callers must update_summary afterwards.
+    fn raw_words_mut(&mut self) -> &mut [W] {
         unsafe {
             let chunks_ptr = if self.chunk_count == N {
                 self.packed_chunk_storage.stack_chunks.as_mut_ptr()
@@ -233,18 +321,27 @@ impl<const N: usize> IndexSet<N> {
             std::slice::from_raw_parts_mut(chunks_ptr, self.chunk_count)
         }
     }
+    /// Returns the number of stored chunks, excluding trailing zero chunks.
+    fn trimmed_word_count(&self) -> usize {
+        self.as_words().iter().rposition(|&chunk| chunk != W::ZERO).map_or(0, |idx| idx + 1)
+    }
 
     /// Removes the given index from the set. Returns whether it was present before i.e. the set has changed.
     pub fn remove(&mut self, index: Index) -> bool {
-        let cba = ChunkBitAddr::from_bit_idx(index);
-        self.as_chunks_mut()
-            .get_mut(cba.idx_of_chunk)
+        let (idx_of_chunk, mask) = chunk_addr::<W>(index);
+        let was_set = self
+            .raw_words_mut()
+            .get_mut(idx_of_chunk)
             .map(|chunk| {
-                let was_set = *chunk & cba.chunk_mask() != 0;
-                *chunk &= !cba.chunk_mask();
+                let was_set = *chunk & mask != W::ZERO;
+                *chunk &= !mask;
                 was_set
             })
-            .unwrap_or(false)
+            .unwrap_or(false);
+        if was_set {
+            self.update_summary(idx_of_chunk..idx_of_chunk + 1);
+        }
+        was_set
     }
     fn size_accomodating_chunk_idx(idx_of_chunk: usize) -> usize {
         idx_of_chunk
@@ -262,128 +359,331 @@ impl<const N: usize> IndexSet<N> {
         }
     }
 
-    /// Resizes the chunk storage, trucating chunks if chunk_count < self.chunk_count().
-    /// Afterwards, has the same chunks as before in [0..chunk_count] and self.chunk_count() == chunk_count.
-    pub fn resize_chunks_to(&mut self, chunk_count: usize) {
-        let mut new = Self::with_chunk_capacity(chunk_count);
-        for (src, dest) in self.as_chunks().iter().zip(new.as_chunks_mut()) {
+    /// Resizes the storage to word_count words of type W, trucating words if word_count < self.word_capacity().
+    /// Afterwards, has the same words as before in [0..word_count] and self.word_capacity() == word_count.
+    pub fn resize_words_to(&mut self, word_count: usize) {
+        if !self.try_resize_words_to(word_count) {
+            // heap_layout panics if word_count is too large for any allocation
+            std::alloc::handle_alloc_error(Self::heap_layout(word_count))
+        }
+    }
+    /// Like resize_words_to, but returns false, leaving the set unchanged, if the allocation fails.
+    fn try_resize_words_to(&mut self, chunk_count: usize) -> bool {
+        let mut new = match Self::try_with_word_capacity(chunk_count) {
+            Some(new) => new,
+            None => return false,
+        };
+        for (src, dest) in self.as_words().iter().zip(new.as_words_mut()) {
             *dest = *src;
         }
+        let had_summary = self.has_summary();
         *self = new; // drops current
+        if had_summary {
+            self.enable_summary();
+        }
+        true
     }
-    /// afterwards has same contents but idx_of_chunk < self.chunk_count()
-    pub fn resize_chunks_to_accomodate(&mut self, idx_of_chunk: usize) {
-        self.resize_chunks_to(Self::size_accomodating_chunk_idx(idx_of_chunk))
+    /// afterwards has same contents but idx_of_word < self.word_capacity(), counting words of type W
+    pub fn resize_words_to_accomodate(&mut self, idx_of_word: usize) {
+        self.resize_words_to(Self::size_accomodating_chunk_idx(idx_of_word))
     }
     /// Adds the given index to the set. Returns whether it was absent before i.e. the set has changed.
+    /// Grows the chunk storage to accomodate bit_idx, so very large indices need very large allocations.
+    /// PagedIndexSet and RoaringIndexSet are better suited to sets with very large indices.
     pub fn insert(&mut self, bit_idx: usize) -> bool {
-        let cba = ChunkBitAddr::from_bit_idx(bit_idx);
-        if self.chunk_count <= cba.idx_of_chunk {
-            self.resize_chunks_to_accomodate(cba.idx_of_chunk)
+        let (idx_of_chunk, mask) = chunk_addr::<W>(bit_idx);
+        if self.chunk_count <= idx_of_chunk {
+            self.resize_words_to_accomodate(idx_of_chunk)
         }
         let chunk = unsafe {
             // certainly in bounds
-            self.as_chunks_mut().get_unchecked_mut(cba.idx_of_chunk)
+            self.raw_words_mut().get_unchecked_mut(idx_of_chunk)
         };
-        let was_unset = *chunk & cba.chunk_mask() == 0;
-        *chunk |= cba.chunk_mask();
+        let was_unset = *chunk & mask == W::ZERO;
+        *chunk |= mask;
+        if was_unset {
+            self.update_summary(idx_of_chunk..idx_of_chunk + 1);
+        }
         was_unset
     }
     /// Equivalent to for i in r.iter_indexes() { self.insert(i); }
     pub fn insert_all<R: ChunkRead + ?Sized>(&mut self, r: &R) {
-        for idx_of_chunk in 0.. {
-            if let Some(read_chunk) = r.get_chunk(idx_of_chunk) {
-                if read_chunk != 0 {
-                    if self.chunk_count <= idx_of_chunk {
-                        self.resize_chunks_to_accomodate(idx_of_chunk)
-                    }
-                    let write_chunk = unsafe {
-                        // certainly in bounds
-                        self.as_chunks_mut().get_unchecked_mut(idx_of_chunk)
-                    };
-                    *write_chunk |= read_chunk;
-                }
-            } else {
-                return;
+        for (idx_of_read_chunk, read_chunk) in r.iter_nonzero_chunks() {
+            // the chunk storing the greatest index of read_chunk
+            let max_in_read_chunk = usize::BITS - 1 - read_chunk.leading_zeros();
+            let max = idx_of_read_chunk * usize::BITS as usize + max_in_read_chunk as usize;
+            let (idx_of_chunk, _) = chunk_addr::<W>(max);
+            if self.chunk_count <= idx_of_chunk {
+                self.resize_words_to_accomodate(idx_of_chunk)
             }
+            update_chunk_in_words(self.raw_words_mut(), idx_of_read_chunk, |c| c | read_chunk);
+            self.update_summary(self.chunk_range_of(idx_of_read_chunk));
         }
     }
-    /// Equivalent to for i in r.iter_indexes() { self.insert(i); }
-    pub fn remove_all<R: ChunkRead>(&mut self, r: &R) {
-        for (idx_of_chunk, write_chunk) in self.as_chunks_mut().iter_mut().enumerate() {
-            if let Some(read_chunk) = r.get_chunk(idx_of_chunk) {
-                if read_chunk != 0 {
-                    *write_chunk &= !read_chunk;
-                }
-            } else {
+    /// Equivalent to for i in r.iter_indexes() { self.remove(i); }
+    pub fn remove_all<R: ChunkRead + ?Sized>(&mut self, r: &R) {
+        for (idx_of_read_chunk, read_chunk) in r.iter_nonzero_chunks() {
+            let chunk_range = self.chunk_range_of(idx_of_read_chunk);
+            if chunk_range.is_empty() {
                 return;
             }
+            update_chunk_in_words(self.raw_words_mut(), idx_of_read_chunk, |c| c & !read_chunk);
+            self.update_summary(chunk_range);
         }
     }
+    /// Returns the range of stored chunks overlapping the Chunk read at idx_of_read_chunk.
+    fn chunk_range_of(&self, idx_of_read_chunk: usize) -> Range<usize> {
+        let range = word_range_of_chunk::<W>(idx_of_read_chunk);
+        range.start.min(self.chunk_count)..range.end.min(self.chunk_count)
+    }
     /// Minimizes self.chunk_count() without changing the set's elements.
     pub fn shrink_to_fit(&mut self) {
-        let new_chunk_count = self.zero_chunks_from_exact();
+        let new_chunk_count = self.trimmed_word_count();
         if new_chunk_count < self.chunk_count {
-            let mut new = Self::with_chunk_capacity(new_chunk_count);
-            for (dest, src) in new.as_chunks_mut().iter_mut().zip(self.as_chunks().iter()) {
-                *dest = *src;
-            }
+            self.resize_words_to(new_chunk_count);
         }
     }
     /// Leaves chunks unchanged. Afterwards, containits no indexes
     pub fn clear(&mut self) {
-        for chunk in self.as_chunks_mut() {
-            *chunk = 0;
+        for chunk in self.raw_words_mut() {
+            *chunk = W::ZERO;
+        }
+        self.update_summary(0..self.chunk_count);
+    }
+    /// Increases each element by k, growing the chunk capacity as necessary.
+    /// Panics, leaving the set unchanged, if the grown chunk storage cannot be allocated.
+    /// In-place equivalent to `*self = self.shifted_by(k).to_index_set()`.
+    pub fn shift_left(&mut self, k: Index) {
+        let zcf = self.trimmed_word_count();
+        if zcf == 0 {
+            return;
+        }
+        let bits = W::BITS as usize;
+        let (by_chunks, by_bits) = (k / bits, (k % bits) as u32);
+        let spill = (by_bits > 0) as usize;
+        // a huge k needs more chunks than can be counted, or allocated
+        let needed = zcf.checked_add(by_chunks).and_then(|needed| needed.checked_add(spill));
+        let needed = match needed {
+            Some(needed) if needed <= self.chunk_count || self.try_resize_words_to(needed) => {
+                needed
+            }
+            _ => panic!("shift_left by {} needs more chunks than can be allocated", k),
+        };
+        let chunks = self.raw_words_mut();
+        // descending, s.t. each src chunk is read before it is overwritten
+        for idx_of_chunk in (0..needed).rev() {
+            chunks[idx_of_chunk] = match idx_of_chunk.checked_sub(by_chunks) {
+                None => W::ZERO,
+                Some(idx_of_src) if by_bits == 0 => chunks[idx_of_src],
+                Some(idx_of_src) => {
+                    let below = match idx_of_src.checked_sub(1) {
+                        Some(idx_of_below) => chunks[idx_of_below],
+                        None => W::ZERO,
+                    };
+                    let src = if idx_of_src < zcf { chunks[idx_of_src] } else { W::ZERO };
+                    src << by_bits | below >> (W::BITS - by_bits)
+                }
+            };
+        }
+        self.update_summary(0..needed);
+    }
+    /// Decreases each element by k, removing those less than k. Does not change the chunk capacity.
+    /// In-place equivalent to `*self = self.shifted_down_by(k).to_index_set()`.
+    pub fn shift_right(&mut self, k: Index) {
+        let bits = W::BITS as usize;
+        let (by_chunks, by_bits) = (k / bits, (k % bits) as u32);
+        let chunk_count = self.chunk_count;
+        let chunks = self.raw_words_mut();
+        // ascending, s.t. each src chunk is read before it is overwritten
+        for idx_of_chunk in 0..chunk_count {
+            let idx_of_src = idx_of_chunk.saturating_add(by_chunks);
+            let src = chunks.get(idx_of_src).copied().unwrap_or(W::ZERO);
+            chunks[idx_of_chunk] = if by_bits == 0 {
+                src
+            } else {
+                let above = chunks.get(idx_of_src.saturating_add(1)).copied().unwrap_or(W::ZERO);
+                src >> by_bits | above << (W::BITS - by_bits)
+            };
+        }
+        self.update_summary(0..chunk_count);
+    }
+    /// For each weight w in turn, adds i + w for each element i, as in `*self |= &self.shifted_by(w)`,
+    /// but only for sums less than bound. Existing elements are kept, even those not less than bound.
+    /// Starting from {0}, the result is the set of totals of sub-multisets of the weights (less than bound).
+    pub fn subset_sum_reachability<I: IntoIterator<Item = Index>>(
+        &mut self,
+        weights: I,
+        bound: Index,
+    ) {
+        let bits = W::BITS as usize;
+        let chunk_count = bound.div_ceil(bits);
+        if self.chunk_count < chunk_count {
+            self.resize_words_to(chunk_count);
         }
+        let (end_chunk, end_bits) = (bound / bits, (bound % bits) as u32);
+        let chunks = self.raw_words_mut();
+        for w in weights.into_iter().filter(|&w| w < bound) {
+            let (by_chunks, by_bits) = (w / bits, (w % bits) as u32);
+            // descending, s.t. each src chunk is read before it is updated
+            for idx_of_chunk in (by_chunks..chunk_count).rev() {
+                let idx_of_src = idx_of_chunk - by_chunks;
+                let shifted = if by_bits == 0 {
+                    chunks[idx_of_src]
+                } else {
+                    let below = match idx_of_src.checked_sub(1) {
+                        Some(idx_of_below) => chunks[idx_of_below],
+                        None => W::ZERO,
+                    };
+                    chunks[idx_of_src] << by_bits | below >> (W::BITS - by_bits)
+                };
+                let mask = if idx_of_chunk == end_chunk { !(W::MAX << end_bits) } else { W::MAX };
+                chunks[idx_of_chunk] |= shifted & mask;
+            }
+        }
+        self.update_summary(0..chunk_count);
     }
     /// in-place equivalent to {
     ///   *self = self.clone().combined(op, other).to_index_set();
     ///   self.shrink_to_fit();
     /// }
-    pub fn overwrite_from_combination<O: BinChunkOp, A: ChunkRead>(&mut self, op: O, other: &A) {
+    pub fn overwrite_from_combination<O: BinChunkOp, A: ChunkRead + ?Sized>(
+        &mut self,
+        op: O,
+        other: &A,
+    ) {
         let zcf = op.combine_readers(self, other).zero_chunks_from_exact();
-        if self.chunk_count < zcf {
-            self.resize_chunks_to(zcf);
-        }
-        for (idx_of_chunk, write_chunk) in self.as_chunks_mut().iter_mut().enumerate() {
-            if let Some(combined) =
-                op.combine_chunks(Some(*write_chunk), other.get_chunk(idx_of_chunk))
-            {
-                *write_chunk = combined;
+        let needed = match zcf.checked_sub(1) {
+            Some(idx_of_last_read_chunk) => word_range_of_chunk::<W>(idx_of_last_read_chunk).end,
+            None => 0,
+        };
+        if self.chunk_count < needed {
+            self.resize_words_to(needed);
+        }
+        let read_chunk_count = chunk_count_of_words::<W>(self.chunk_count);
+        let chunks = self.raw_words_mut();
+        for idx_of_read_chunk in 0..read_chunk_count {
+            update_chunk_in_words(chunks, idx_of_read_chunk, |chunk| {
+                // None means the combined chunk is zero
+                op.combine_chunks(Some(chunk), other.get_chunk(idx_of_read_chunk)).unwrap_or(0)
+            });
+        }
+        self.update_summary(0..self.chunk_count);
+    }
+    /// Reads a set in the portable binary format written by ChunkRead::write_to.
+    /// See the encoding module for the format and the errors that may occur.
+    pub fn read_from<R: std::io::Read>(
+        reader: &mut R,
+    ) -> Result<Self, crate::encoding::DecodeError> {
+        let words = crate::encoding::read_words(reader)?;
+        let chunks: Vec<Chunk> = crate::chunks_from_u64_words(&words).collect();
+        Ok(Self::from_chunk_read(&chunks[..]))
+    }
+    /// Starts maintaining a summary of which chunks are nonzero, s.t. next_nonzero_chunk_from,
+    /// and everything built on it (iteration, is_empty, len, combinators, ...) skip runs of zero chunks.
+    /// Costs one extra bit per chunk (plus a vanishing amount for higher levels), kept up to date by
+    /// insert, remove, insert_all_in_range, and the other mutating methods.
+    pub fn enable_summary(&mut self) {
+        self.summary = Some(Box::new(ChunkSummary::new(self.as_words())));
+    }
+    /// Stops maintaining a summary, freeing its memory.
+    pub fn disable_summary(&mut self) {
+        self.summary = None;
+    }
+    /// Returns whether this set maintains a summary of its nonzero chunks. See enable_summary.
+    pub fn has_summary(&self) -> bool {
+        self.summary.is_some()
+    }
+    /// Brings the summary (if any) up to date after the chunks in chunk_range were written.
+    fn update_summary(&mut self, chunk_range: Range<usize>) {
+        if let Some(mut summary) = self.summary.take() {
+            let chunks = self.as_words();
+            if summary.stale {
+                summary.rebuild(chunks);
+            } else {
+                for idx_of_chunk in chunk_range {
+                    summary.update(idx_of_chunk, chunks[idx_of_chunk] != W::ZERO);
+                }
             }
+            self.summary = Some(summary);
+        }
+    }
+    /// Returns the index of the next nonzero stored chunk at or after idx_of_chunk.
+    fn next_nonzero_word_from(&self, idx_of_chunk: usize) -> Option<usize> {
+        let chunks = self.as_words();
+        match &self.summary {
+            Some(summary) if !summary.stale => summary.next_nonzero_chunk_from(idx_of_chunk),
+            _ => Some(
+                idx_of_chunk
+                    + chunks.get(idx_of_chunk..)?.iter().position(|&chunk| chunk != W::ZERO)?,
+            ),
         }
     }
 }
-// impl<const N: usize> IndexSubsetIter<N> {
-//     pub fn next_subset(&mut self) -> Option<&IndexSet<N>> {
-//         if self.already_returned_buffered {
-//             if !self.buffered.try_decrease_in_powerset_order() {
-//                 return None;
-//             }
-//         } else {
-//             self.already_returned_buffered = true;
-//         }
-//         Some(&self.buffered)
-//     }
-// }
-impl<const N: usize> ChunkRead for IndexSet<N> {
+/// The chunk-named storage methods, only for IndexSets storing Chunks, whose words are Chunks.
+/// Each is the same as its word-named counterpart.
+impl<const N: usize> IndexSet<N, Chunk> {
+    /// Returns the IndexSet's chunk capacity
+    pub fn chunk_capacity(&self) -> usize {
+        self.word_capacity()
+    }
+    /// Creates an empty IndexSet with the given chunk capacity
+    pub fn with_chunk_capacity(chunk_count: usize) -> Self {
+        Self::with_word_capacity(chunk_count)
+    }
+    /// Creates an IndexSet from a sequence of contiguous chunks.
+    /// Result may have greater capacity than the number of chunks provided.
+    pub fn from_chunk_iter<I: IntoIterator<Item = Chunk>>(it: I) -> Self {
+        Self::from_word_iter(it)
+    }
+    /// Creates an IndexSet from a sequence of contiguous chunks.
+    /// Result has a chunk capacity equivalent to the length of the slice.
+    pub fn from_chunk_slice(chunks: &[Chunk]) -> Self {
+        Self::from_word_slice(chunks)
+    }
+    /// Returns an immutable slice of the stored Chunks
+    pub fn as_chunks(&self) -> &[Chunk] {
+        self.as_words()
+    }
+    /// Returns a mutable slice of the stored Chunks.
+    /// If this set maintains a summary, it is rebuilt lazily upon the next mutation via another method.
+    pub fn as_chunks_mut(&mut self) -> &mut [Chunk] {
+        self.as_words_mut()
+    }
+    /// Resizes the chunk storage, trucating chunks if chunk_count < self.chunk_count().
+    /// Afterwards, has the same chunks as before in [0..chunk_count] and self.chunk_count() == chunk_count.
+    pub fn resize_chunks_to(&mut self, chunk_count: usize) {
+        self.resize_words_to(chunk_count)
+    }
+    /// afterwards has same contents but idx_of_chunk < self.chunk_count()
+    pub fn resize_chunks_to_accomodate(&mut self, idx_of_chunk: usize) {
+        self.resize_words_to_accomodate(idx_of_chunk)
+    }
+}
+impl<const N: usize, W: Word> ChunkRead for IndexSet<N, W> {
     fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
-        self.as_chunks().get(idx_of_chunk).copied()
+        chunk_from_words(self.as_words(), idx_of_chunk)
     }
     fn zero_chunks_from_conservative(&self) -> usize {
-        self.chunk_count
+        chunk_count_of_words::<W>(self.chunk_count)
+    }
+    fn next_nonzero_chunk_from(&self, mut idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        loop {
+            // the stored chunks overlapping the read chunk at idx_of_chunk start here
+            let idx_of_word = word_range_of_chunk::<W>(idx_of_chunk).start;
+            let idx_of_word = self.next_nonzero_word_from(idx_of_word)?;
+            idx_of_chunk = idx_of_chunk.max(first_chunk_of_word::<W>(idx_of_word));
+            // a stored chunk wider than a Chunk may be nonzero only in later read chunks
+            match self.get_chunk(idx_of_chunk)? {
+                0 => idx_of_chunk += 1,
+                chunk => return Some((idx_of_chunk, chunk)),
+            }
+        }
     }
 }
-impl<const N: usize> Drop for IndexSet<N> {
+impl<const N: usize, W: Word> Drop for IndexSet<N, W> {
     fn drop(&mut self) {
         if N < self.chunk_count {
-            let layout = unsafe {
-                // will be valid layout. Cannot be zero. cannot be misaligned. Cannot be too large.
-                std::alloc::Layout::from_size_align_unchecked(
-                    CHUNK_BYTES * self.chunk_count,
-                    CHUNK_BYTES,
-                )
-            };
+            let layout = Self::heap_layout(self.chunk_count);
             unsafe {
                 // layout matches allocation
                 std::alloc::dealloc(self.packed_chunk_storage.heap_chunks_ptr as *mut u8, layout)
@@ -391,7 +691,7 @@ impl<const N: usize> Drop for IndexSet<N> {
         }
     }
 }
-impl<const N: usize> FromIterator<Index> for IndexSet<N> {
+impl<const N: usize, W: Word> FromIterator<Index> for IndexSet<N, W> {
     fn from_iter<I: IntoIterator<Item = Index>>(into_iter: I) -> Self {
         let mut m = Self::default();
         for index in into_iter.into_iter() {
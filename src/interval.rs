@@ -0,0 +1,266 @@
+use super::{chunk_mask_of_bits, index_count_to_chunk_count, Chunk, ChunkRead, Index};
+use core::fmt::Debug;
+use core::iter::FromIterator;
+use core::ops::{Range, RangeInclusive};
+
+/// Stores a set of indices as a sorted list of maximal runs of consecutive indices.
+/// Compact for sets made of few, long runs, regardless of their length or magnitude.
+/// Chunks are synthesized on demand, so IntervalSet interoperates with every other ChunkRead.
+/// Index::MAX is not the start of any Range<Index>, so it is stored separately from the runs.
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+pub struct IntervalSet {
+    // invariants: every run is nonempty.
+    // runs are sorted, and neither overlap nor touch, i.e., runs[i].end < runs[i+1].start.
+    runs: Vec<Range<Index>>,
+    // whether Index::MAX is an element
+    contains_max: bool,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates an IntervalSet with the same elements as the given reader.
+    pub fn from_chunk_read<R: ChunkRead + ?Sized>(r: &R) -> Self {
+        // r's runs are already maximal, sorted, and disjoint
+        let mut me = Self::new();
+        for run in r.iter_runs() {
+            let (start, last) = run.into_inner();
+            match last.checked_add(1) {
+                Some(end) => me.runs.push(start..end),
+                None => {
+                    me.push_run(start..Index::MAX);
+                    me.contains_max = true;
+                }
+            }
+        }
+        me
+    }
+    /// Appends a run which starts at or after the end of the last run
+    fn push_run(&mut self, run: Range<Index>) {
+        match self.runs.last_mut() {
+            _ if run.is_empty() => {}
+            Some(last) if run.start <= last.end => last.end = last.end.max(run.end),
+            _ => self.runs.push(run),
+        }
+    }
+    /// Returns the maximal runs of consecutive elements, in ascending order.
+    /// Excludes Index::MAX, which no Range<Index> contains. See contains.
+    pub fn runs(&self) -> &[Range<Index>] {
+        &self.runs
+    }
+    /// Iterates over the maximal runs of consecutive elements as inclusive ranges, in ascending order.
+    /// Unlike runs, includes Index::MAX, like ChunkRead::iter_runs.
+    pub fn iter_runs(&self) -> impl DoubleEndedIterator<Item = RangeInclusive<Index>> + '_ {
+        // Index::MAX either extends the last run, or is a run of its own
+        let max_extends_last =
+            self.contains_max && self.runs.last().is_some_and(|run| run.end == Index::MAX);
+        let max_run = (self.contains_max && !max_extends_last).then_some(Index::MAX..=Index::MAX);
+        let last_run_idx = self.runs.len().wrapping_sub(1);
+        self.runs
+            .iter()
+            .enumerate()
+            .map(move |(i, run)| {
+                let last =
+                    if i == last_run_idx && max_extends_last { Index::MAX } else { run.end - 1 };
+                run.start..=last
+            })
+            .chain(max_run)
+    }
+    /// Adds the given index to the set. Returns whether it was absent before i.e. the set has changed.
+    pub fn insert(&mut self, index: Index) -> bool {
+        let was_unset = !self.contains(index);
+        match index.checked_add(1) {
+            Some(end) if was_unset => self.insert_all_in_range(index..end),
+            _ => self.contains_max |= index == Index::MAX,
+        }
+        was_unset
+    }
+    /// Removes the given index from the set. Returns whether it was present before i.e. the set has changed.
+    pub fn remove(&mut self, index: Index) -> bool {
+        let was_set = self.contains(index);
+        match index.checked_add(1) {
+            Some(end) if was_set => self.remove_all_in_range(index..end),
+            _ => self.contains_max &= index != Index::MAX,
+        }
+        was_set
+    }
+    /// Adds all indices in the given range to the set.
+    pub fn insert_all_in_range(&mut self, mut range: Range<Index>) {
+        if range.is_empty() {
+            return;
+        }
+        // runs overlapping or touching range are merged into it
+        let start = self.runs.partition_point(|run| run.end < range.start);
+        let end = self.runs.partition_point(|run| run.start <= range.end);
+        if start < end {
+            range.start = range.start.min(self.runs[start].start);
+            range.end = range.end.max(self.runs[end - 1].end);
+        }
+        self.runs.splice(start..end, [range]);
+    }
+    /// Removes all indices in the given range from the set.
+    pub fn remove_all_in_range(&mut self, range: Range<Index>) {
+        if range.is_empty() {
+            return;
+        }
+        // runs overlapping range are removed, but for the parts sticking out of either end
+        let start = self.runs.partition_point(|run| run.end <= range.start);
+        let end = self.runs.partition_point(|run| run.start < range.end);
+        if start < end {
+            let before = self.runs[start].start..range.start;
+            let after = range.end..self.runs[end - 1].end;
+            let kept = [before, after].into_iter().filter(|run| !run.is_empty());
+            self.runs.splice(start..end, kept);
+        }
+    }
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.runs.clear();
+        self.contains_max = false;
+    }
+    /// Returns the set of indices in self or other, computed directly from their runs.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        let (mut a, mut b) = (self.runs.iter().peekable(), other.runs.iter().peekable());
+        loop {
+            let next = match (a.peek(), b.peek()) {
+                (Some(ra), Some(rb)) if ra.start <= rb.start => a.next(),
+                (Some(_), Some(_)) => b.next(),
+                (Some(_), None) => a.next(),
+                (None, _) => b.next(),
+            };
+            match next {
+                Some(run) => result.push_run(run.clone()),
+                None => break,
+            }
+        }
+        result.contains_max = self.contains_max || other.contains_max;
+        result
+    }
+    /// Returns the set of indices in both self and other, computed directly from their runs.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(ra), Some(rb)) = (self.runs.get(i), other.runs.get(j)) {
+            result.push_run(ra.start.max(rb.start)..ra.end.min(rb.end));
+            // the run ending first cannot overlap any later runs of the other
+            if ra.end <= rb.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        result.contains_max = self.contains_max && other.contains_max;
+        result
+    }
+    /// Returns the set of indices in self but not in other, computed directly from their runs.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        let mut j = 0;
+        for run in self.runs.iter() {
+            let mut start = run.start;
+            // skip other's runs that end before this run
+            while other.runs.get(j).is_some_and(|rb| rb.end <= start) {
+                j += 1;
+            }
+            let mut k = j;
+            while let Some(rb) = other.runs.get(k).filter(|rb| rb.start < run.end) {
+                result.push_run(start..rb.start.max(start));
+                start = start.max(rb.end);
+                k += 1;
+            }
+            if start < run.end {
+                result.push_run(start..run.end);
+            }
+        }
+        result.contains_max = self.contains_max && !other.contains_max;
+        result
+    }
+}
+
+impl ChunkRead for IntervalSet {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        if self.zero_chunks_from_conservative() <= idx_of_chunk {
+            return None;
+        }
+        let lo = idx_of_chunk * usize::BITS as usize;
+        // the last chunk ends at Index::MAX, which the runs cannot contain
+        let hi = lo.saturating_add(usize::BITS as usize);
+        let start = self.runs.partition_point(|run| run.end <= lo);
+        let overlapping = self.runs[start..].iter().take_while(|run| run.start < hi);
+        let chunk = overlapping.fold(0, |chunk, run| {
+            let bits = (run.start.max(lo) - lo) as u32..(run.end.min(hi) - lo) as u32;
+            chunk | chunk_mask_of_bits(bits)
+        });
+        let max_bit =
+            if self.contains_max && hi == Index::MAX { 1 << (usize::BITS - 1) } else { 0 };
+        Some(chunk | max_bit)
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        if self.contains_max {
+            return index_count_to_chunk_count(Index::MAX);
+        }
+        self.runs.last().map(|run| index_count_to_chunk_count(run.end)).unwrap_or(0)
+    }
+    fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        let lo = idx_of_chunk.checked_mul(usize::BITS as usize)?;
+        let start = match self.runs.get(self.runs.partition_point(|run| run.end <= lo)) {
+            Some(run) => run.start.max(lo),
+            None if self.contains_max => Index::MAX,
+            None => return None,
+        };
+        let idx_of_chunk = start / usize::BITS as usize;
+        Some((idx_of_chunk, self.get_chunk(idx_of_chunk)?))
+    }
+    fn contains(&self, index: Index) -> bool {
+        if index == Index::MAX {
+            return self.contains_max;
+        }
+        let i = self.runs.partition_point(|run| run.start <= index);
+        i > 0 && index < self.runs[i - 1].end
+    }
+    fn len(&self) -> usize {
+        self.runs.iter().map(|run| run.len()).sum::<usize>() + self.contains_max as usize
+    }
+    fn is_empty(&self) -> bool {
+        self.runs.is_empty() && !self.contains_max
+    }
+    fn min_element(&self) -> Option<Index> {
+        match self.runs.first() {
+            Some(run) => Some(run.start),
+            None => self.contains_max.then_some(Index::MAX),
+        }
+    }
+    fn max_element(&self) -> Option<Index> {
+        if self.contains_max {
+            return Some(Index::MAX);
+        }
+        self.runs.last().map(|run| run.end - 1)
+    }
+}
+
+impl Debug for IntervalSet {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let max = self.contains_max.then_some(Index::MAX..=Index::MAX);
+        f.debug_set().entries(self.runs.iter()).entries(max).finish()
+    }
+}
+impl FromIterator<Index> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Index>>(into_iter: I) -> Self {
+        let mut m = Self::default();
+        for index in into_iter.into_iter() {
+            m.insert(index);
+        }
+        m
+    }
+}
+impl FromIterator<Range<Index>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Range<Index>>>(into_iter: I) -> Self {
+        let mut m = Self::default();
+        for range in into_iter.into_iter() {
+            m.insert_all_in_range(range);
+        }
+        m
+    }
+}
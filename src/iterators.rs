@@ -1,47 +1,290 @@
 use super::*;
+use core::ops::{Bound, Range, RangeBounds, RangeInclusive};
 
 #[derive(Debug, Copy, Clone)]
 pub struct ChunkIter<'a, A: ChunkRead + ?Sized> {
     a: &'a A,
     idx_of_next_chunk: usize,
+    // exclusive bound on chunks yet to be yielded.
+    // None until bounded by a range or computed by the first call to next_back.
+    idx_of_end_chunk: Option<usize>,
 }
 
+/// Yields (idx_of_chunk, chunk) for each nonzero chunk, skipping zero chunks.
+#[derive(Debug, Copy, Clone)]
+pub struct NonzeroChunkIter<'a, A: ChunkRead + ?Sized> {
+    a: &'a A,
+    idx_of_next_chunk: usize,
+}
+
+/// Yields each maximal run of consecutive elements as an inclusive range, in ascending order.
+/// Inclusive, s.t. a run containing Index::MAX can be represented too.
+#[derive(Debug, Copy, Clone)]
+pub struct RunIter<'a, A: ChunkRead + ?Sized> {
+    a: &'a A,
+    // (idx_of_chunk, not-yet-reported elements of that chunk)
+    cached: (usize, Chunk),
+    idx_of_next_chunk: usize,
+}
+
+/// Yields each maximal run of consecutive non-elements below some bound as a range, in ascending order.
+#[derive(Debug, Copy, Clone)]
+pub struct GapIter<'a, A: ChunkRead + ?Sized> {
+    runs: RunIter<'a, A>,
+    // None once exhausted
+    next_gap_start: Option<Index>,
+    bound: Index,
+}
+
+/// Yields the elements of a ChunkRead in ascending (or, from the back, descending) order,
+/// optionally restricted to some range of indices.
 #[derive(Debug, Copy, Clone)]
 pub struct IndexIter<'a, A: ChunkRead + ?Sized> {
     pub(crate) wi: ChunkIter<'a, A>,
-    pub(crate) cached: usize,
+    // (idx_of_chunk, not-yet-yielded elements of that chunk) at the front and back.
+    pub(crate) cached: (usize, Chunk),
+    pub(crate) cached_back: (usize, Chunk),
+    // inclusive bounds on the yielded indices
+    first: Index,
+    last: Index,
 }
 
 impl<'a, A: ChunkRead + ?Sized> ChunkIter<'a, A> {
     pub fn new(a: &'a A) -> Self {
-        Self { a, idx_of_next_chunk: 0 }
+        Self { a, idx_of_next_chunk: 0, idx_of_end_chunk: None }
+    }
+    /// Iterates over only the chunks in the given range of chunk indices
+    pub fn with_chunk_range(a: &'a A, chunk_range: Range<usize>) -> Self {
+        // no need to look beyond the reader's (conservative) end
+        let end = chunk_range.end.min(a.zero_chunks_from_conservative());
+        Self { a, idx_of_next_chunk: chunk_range.start, idx_of_end_chunk: Some(end) }
+    }
+    fn idx_of_end_chunk(&mut self) -> usize {
+        let a = self.a;
+        *self.idx_of_end_chunk.get_or_insert_with(|| a.zero_chunks_from_exact())
+    }
+    /// Like next, but skips zero chunks, returning (idx_of_chunk, chunk).
+    pub(crate) fn next_nonzero(&mut self) -> Option<(usize, Chunk)> {
+        if self.idx_of_end_chunk.is_some_and(|end| end <= self.idx_of_next_chunk) {
+            // don't fetch chunks beyond the end, only to discard them
+            return None;
+        }
+        let next = self.a.next_nonzero_chunk_from(self.idx_of_next_chunk);
+        match (next, self.idx_of_end_chunk) {
+            (Some((idx_of_chunk, chunk)), Some(end)) if idx_of_chunk < end => {
+                self.idx_of_next_chunk = idx_of_chunk + 1;
+                Some((idx_of_chunk, chunk))
+            }
+            (Some((idx_of_chunk, chunk)), None) => {
+                self.idx_of_next_chunk = idx_of_chunk + 1;
+                Some((idx_of_chunk, chunk))
+            }
+            _ => {
+                // every remaining chunk is zero
+                self.idx_of_next_chunk = self.idx_of_end_chunk.unwrap_or(usize::MAX);
+                None
+            }
+        }
     }
 }
 
 impl<A: ChunkRead + ?Sized> Iterator for ChunkIter<'_, A> {
     type Item = usize;
     fn next(&mut self) -> Option<usize> {
+        if let Some(end) = self.idx_of_end_chunk {
+            if end <= self.idx_of_next_chunk {
+                return None;
+            }
+        }
         let next = self.a.get_chunk(self.idx_of_next_chunk)?;
         self.idx_of_next_chunk += 1;
         Some(next)
     }
 }
 
+impl<A: ChunkRead + ?Sized> DoubleEndedIterator for ChunkIter<'_, A> {
+    fn next_back(&mut self) -> Option<usize> {
+        let end = self.idx_of_end_chunk();
+        if end <= self.idx_of_next_chunk {
+            return None;
+        }
+        self.idx_of_end_chunk = Some(end - 1);
+        Some(self.a.get_chunk(end - 1).unwrap_or(0))
+    }
+}
+
+impl<'a, A: ChunkRead + ?Sized> NonzeroChunkIter<'a, A> {
+    pub fn new(a: &'a A) -> Self {
+        Self { a, idx_of_next_chunk: 0 }
+    }
+}
+
+impl<A: ChunkRead + ?Sized> Iterator for NonzeroChunkIter<'_, A> {
+    type Item = (usize, Chunk);
+    fn next(&mut self) -> Option<(usize, Chunk)> {
+        let (idx_of_chunk, chunk) = self.a.next_nonzero_chunk_from(self.idx_of_next_chunk)?;
+        self.idx_of_next_chunk = idx_of_chunk + 1;
+        Some((idx_of_chunk, chunk))
+    }
+}
+
 impl<'a, A: ChunkRead + ?Sized> IndexIter<'a, A> {
     pub fn new(a: &'a A) -> Self {
-        Self { wi: a.iter_chunks(), cached: 0 }
+        Self {
+            wi: a.iter_chunks(),
+            cached: (0, 0),
+            cached_back: (0, 0),
+            first: 0,
+            last: Index::MAX,
+        }
+    }
+    /// Iterates over only the elements in the given range of indices
+    pub fn with_range<R: RangeBounds<Index>>(a: &'a A, range: R) -> Self {
+        let first = match range.start_bound() {
+            Bound::Included(&i) => Some(i),
+            Bound::Excluded(&i) => i.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let last = match range.end_bound() {
+            Bound::Included(&i) => Some(i),
+            Bound::Excluded(&i) => i.checked_sub(1),
+            Bound::Unbounded => Some(Index::MAX),
+        };
+        let (first, last, chunk_range) = match (first, last) {
+            (Some(first), Some(last)) if first <= last => {
+                let first_chunk = ChunkBitAddr::from_bit_idx(first).idx_of_chunk;
+                let last_chunk = ChunkBitAddr::from_bit_idx(last).idx_of_chunk;
+                (first, last, first_chunk..last_chunk + 1)
+            }
+            // empty range
+            _ => (0, 0, 0..0),
+        };
+        let wi = ChunkIter::with_chunk_range(a, chunk_range);
+        Self { wi, cached: (0, 0), cached_back: (0, 0), first, last }
+    }
+    /// Clears the bits of the chunk at idx_of_chunk which represent indices outside first..=last.
+    fn mask_to_range(&self, idx_of_chunk: usize, mut chunk: Chunk) -> Chunk {
+        let first = ChunkBitAddr::from_bit_idx(self.first);
+        let last = ChunkBitAddr::from_bit_idx(self.last);
+        if first.idx_of_chunk == idx_of_chunk {
+            chunk &= first.chunk_mask_from();
+        }
+        if last.idx_of_chunk == idx_of_chunk {
+            chunk &= last.chunk_mask_through();
+        }
+        chunk
     }
 }
 impl<A: ChunkRead + ?Sized> Iterator for IndexIter<'_, A> {
     type Item = usize;
     fn next(&mut self) -> Option<usize> {
-        while self.cached == 0 {
-            self.cached = self.wi.next()?;
-        }
-        // self.cached is NONZERO
-        let idx_in_chunk = self.cached.trailing_zeros();
-        self.cached &= !(1 << idx_in_chunk);
-        let cba = ChunkBitAddr { idx_in_chunk, idx_of_chunk: self.wi.idx_of_next_chunk - 1 };
-        Some(cba.to_bit_idx())
+        while self.cached.1 == 0 {
+            // jump straight to the next nonzero chunk
+            self.cached = match self.wi.next_nonzero() {
+                Some((idx_of_chunk, chunk)) => {
+                    (idx_of_chunk, self.mask_to_range(idx_of_chunk, chunk))
+                }
+                // no more chunks between front and back. continue with the back's chunk
+                None if self.cached_back.1 != 0 => core::mem::take(&mut self.cached_back),
+                None => return None,
+            };
+        }
+        // self.cached.1 is NONZERO
+        let (idx_of_chunk, chunk) = &mut self.cached;
+        let idx_in_chunk = chunk.trailing_zeros();
+        *chunk &= !(1 << idx_in_chunk);
+        Some(ChunkBitAddr { idx_in_chunk, idx_of_chunk: *idx_of_chunk }.to_bit_idx())
+    }
+}
+impl<A: ChunkRead + ?Sized> DoubleEndedIterator for IndexIter<'_, A> {
+    fn next_back(&mut self) -> Option<usize> {
+        while self.cached_back.1 == 0 {
+            self.cached_back = match self.wi.next_back() {
+                Some(chunk) => {
+                    let idx_of_chunk = self.wi.idx_of_end_chunk();
+                    (idx_of_chunk, self.mask_to_range(idx_of_chunk, chunk))
+                }
+                // no more chunks between front and back. continue with the front's chunk
+                None if self.cached.1 != 0 => core::mem::take(&mut self.cached),
+                None => return None,
+            };
+        }
+        // self.cached_back.1 is NONZERO
+        let (idx_of_chunk, chunk) = &mut self.cached_back;
+        let idx_in_chunk = usize::BITS - 1 - chunk.leading_zeros();
+        *chunk &= !(1 << idx_in_chunk);
+        Some(ChunkBitAddr { idx_in_chunk, idx_of_chunk: *idx_of_chunk }.to_bit_idx())
+    }
+}
+
+impl<'a, A: ChunkRead + ?Sized> RunIter<'a, A> {
+    pub fn new(a: &'a A) -> Self {
+        Self { a, cached: (0, 0), idx_of_next_chunk: 0 }
+    }
+}
+impl<A: ChunkRead + ?Sized> Iterator for RunIter<'_, A> {
+    type Item = RangeInclusive<Index>;
+    fn next(&mut self) -> Option<RangeInclusive<Index>> {
+        const B: usize = usize::BITS as usize;
+        while self.cached.1 == 0 {
+            self.cached = self.a.next_nonzero_chunk_from(self.idx_of_next_chunk)?;
+            self.idx_of_next_chunk = self.cached.0 + 1;
+        }
+        let (idx_of_chunk, chunk) = self.cached;
+        let start_in_chunk = chunk.trailing_zeros();
+        let end_in_chunk = start_in_chunk + (chunk >> start_in_chunk).trailing_ones();
+        let start = idx_of_chunk * B + start_in_chunk as usize;
+        if end_in_chunk < usize::BITS {
+            // run ends within this chunk
+            self.cached.1 &= !chunk_mask_of_bits(start_in_chunk..end_in_chunk);
+            return Some(start..=idx_of_chunk * B + end_in_chunk as usize - 1);
+        }
+        // run continues into the next chunk(s), if any
+        let mut idx_of_chunk = idx_of_chunk;
+        loop {
+            if idx_of_chunk == Index::MAX / B {
+                // run reaches Index::MAX, the last index of the last chunk. nothing follows
+                self.cached = (idx_of_chunk, 0);
+                self.idx_of_next_chunk = idx_of_chunk + 1;
+                return Some(start..=Index::MAX);
+            }
+            idx_of_chunk += 1;
+            let chunk = self.a.get_chunk(idx_of_chunk).unwrap_or(0);
+            let ones = chunk.trailing_ones();
+            if ones < usize::BITS {
+                self.cached = (idx_of_chunk, chunk & !chunk_mask_of_bits(0..ones));
+                self.idx_of_next_chunk = idx_of_chunk + 1;
+                // ones may be 0, s.t. the run ended with the previous chunk
+                return Some(start..=idx_of_chunk * B + ones as usize - 1);
+            }
+        }
+    }
+}
+
+impl<'a, A: ChunkRead + ?Sized> GapIter<'a, A> {
+    pub fn new(a: &'a A, bound: Index) -> Self {
+        Self { runs: RunIter::new(a), next_gap_start: Some(0), bound }
+    }
+}
+impl<A: ChunkRead + ?Sized> Iterator for GapIter<'_, A> {
+    type Item = Range<Index>;
+    fn next(&mut self) -> Option<Range<Index>> {
+        loop {
+            let start = self.next_gap_start.filter(|&start| start < self.bound)?;
+            match self.runs.next() {
+                Some(run) => {
+                    // None if the run ends at Index::MAX, s.t. no gap follows
+                    self.next_gap_start = run.end().checked_add(1);
+                    if start < *run.start() {
+                        return Some(start..(*run.start()).min(self.bound));
+                    }
+                    // run starts where the gap would, so there is no gap here
+                }
+                None => {
+                    self.next_gap_start = None;
+                    return Some(start..self.bound);
+                }
+            }
+        }
     }
 }
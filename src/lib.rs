@@ -3,14 +3,65 @@ pub use traits::ChunkRead;
 
 mod traits;
 
+mod dynamic;
+pub use dynamic::DynChunkRead;
+
+mod word;
+pub use word::Word;
+
 mod index_set;
 pub use index_set::IndexSet;
 
+mod bit_array;
+#[doc(hidden)]
+pub use bit_array::ConstItem;
+pub use bit_array::{chunk_count_for, BitArray, IndexOutOfBounds};
+
+mod summary;
+
+mod rank;
+pub use rank::{RankDirectory, Ranked};
+
+mod roaring;
+pub use roaring::RoaringIndexSet;
+
+mod paged;
+pub use paged::PagedIndexSet;
+
+mod interval;
+pub use interval::IntervalSet;
+
+mod typed;
+pub use typed::{Idx, TypedIndexSet};
+
+mod subset_map;
+pub use subset_map::SubsetMap;
+
+mod sums;
+pub use sums::sumset;
+
+pub mod encoding;
+
+pub mod notation;
+use notation::RangeNotation;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "serde")]
+pub use serde_impls::SerializableIndexSet;
+
 pub mod combinators;
-use combinators::{BinChunkOp, CombinedChunkReads};
+use combinators::{
+    BinChunkOp, CombinedChunkReads, Complement, ManyChunkOp, MappedChunkReads, ShiftedDown,
+    ShiftedUp, TernChunkOp, UnChunkOp,
+};
+
+mod ops;
+
+pub mod subsets;
 
 pub mod iterators;
-use iterators::{ChunkIter, IndexIter};
+use iterators::{ChunkIter, GapIter, IndexIter, NonzeroChunkIter, RunIter};
 
 #[cfg(test)]
 mod tests;
@@ -26,6 +77,25 @@ macro_rules! index_set {
     }};
 }
 
+/// Builds a BitArray in const context, e.g., `const S: BitArray<64, 1> = const_index_set![0..8, 12, 40..=45];`.
+/// Items are indices, ranges `i..j` and inclusive ranges `i..=j`. Panics (at compile time in consts)
+/// if any item is out of bounds. BITS is inferred, or given first, as in `const_index_set![64; 0..8, 12]`.
+#[macro_export]
+macro_rules! const_index_set {
+    ($bits:expr; $($item:expr),* $(,)?) => {{
+        let m: $crate::BitArray<{ $bits }, { $crate::chunk_count_for($bits) }> =
+            $crate::const_index_set![$($item),*];
+        m
+    }};
+    ($($item:expr),* $(,)?) => {{
+        let m = $crate::BitArray::new();
+        $(
+            let m = $crate::ConstItem($item).insert_into(m);
+        )*
+        m
+    }};
+}
+
 /////////////////////////////////////////////
 
 pub type Chunk = usize; // stores up to usize::BITS Indexes
@@ -39,10 +109,45 @@ struct ChunkBitAddr {
     idx_in_chunk: u32,   // invariant: in 0..usize::BITs
 }
 ///////////////////////////////////////////////////////////////////////
-const CHUNK_BYTES: usize = core::mem::size_of::<Chunk>();
 
 fn index_count_to_chunk_count(index_count: usize) -> usize {
-    (index_count + usize::BITS as usize - 1) / usize::BITS as usize
+    index_count.div_ceil(usize::BITS as usize)
+}
+/// Chunks are at most 64 bits wide, so each u64 word holds a whole number of chunks.
+/// Portable encodings use u64 words, s.t. they are the same regardless of usize::BITS.
+const CHUNKS_PER_U64: usize = 64 / usize::BITS as usize;
+
+/// Returns the number of u64 words needed to encode a's chunks, excluding trailing zero chunks.
+fn u64_word_count<A: ChunkRead + ?Sized>(a: &A) -> usize {
+    a.zero_chunks_from_exact().div_ceil(CHUNKS_PER_U64)
+}
+/// Returns the u64 word at idx_of_word of a's little-endian encoding as u64 words.
+fn u64_word<A: ChunkRead + ?Sized>(a: &A, idx_of_word: usize) -> u64 {
+    (0..CHUNKS_PER_U64).fold(0, |word, j| {
+        let chunk = a.get_chunk(idx_of_word * CHUNKS_PER_U64 + j).unwrap_or(0);
+        word | (chunk as u64) << (j * usize::BITS as usize)
+    })
+}
+/// Inverse of u64_word: returns the chunks encoded by the given u64 words.
+fn chunks_from_u64_words(words: &[u64]) -> impl Iterator<Item = Chunk> + '_ {
+    let chunks_of_word =
+        |word: u64| (0..CHUNKS_PER_U64).map(move |j| (word >> (j * usize::BITS as usize)) as Chunk);
+    words.iter().flat_map(move |&word| chunks_of_word(word))
+}
+/// Returns the mask of the bits at the given positions. Requires bits.end <= usize::BITS.
+fn chunk_mask_of_bits(bits: core::ops::Range<u32>) -> Chunk {
+    if bits.is_empty() {
+        0
+    } else {
+        (!0 >> (usize::BITS - (bits.end - bits.start))) << bits.start
+    }
+}
+/// Returns the position of the k-th lowest set bit of chunk. Requires k < chunk.count_ones().
+fn select_in_chunk(mut chunk: Chunk, k: usize) -> u32 {
+    for _ in 0..k {
+        chunk &= chunk - 1; // clear lowest set bit
+    }
+    chunk.trailing_zeros()
 }
 impl<A: ChunkRead> Display for DisplayableIndexSet<'_, A> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -59,6 +164,26 @@ impl ChunkBitAddr {
     const fn chunk_mask(&self) -> usize {
         1 << self.idx_in_chunk
     }
+    /// Mask of the bits at positions idx_in_chunk.. in the chunk
+    const fn chunk_mask_from(&self) -> usize {
+        !0 << self.idx_in_chunk
+    }
+    /// Mask of the bits at positions ..=idx_in_chunk in the chunk
+    const fn chunk_mask_through(&self) -> usize {
+        !0 >> (usize::BITS - 1 - self.idx_in_chunk)
+    }
+    /// Mask of the bits in the chunk at idx_of_chunk which represent indices in first..=last.
+    /// Requires first.idx_of_chunk <= idx_of_chunk <= last.idx_of_chunk.
+    fn chunk_mask_between(first: Self, last: Self, idx_of_chunk: usize) -> Chunk {
+        let mut mask: Chunk = !0;
+        if idx_of_chunk == first.idx_of_chunk {
+            mask &= first.chunk_mask_from();
+        }
+        if idx_of_chunk == last.idx_of_chunk {
+            mask &= last.chunk_mask_through();
+        }
+        mask
+    }
     fn to_bit_idx(self) -> usize {
         self.idx_of_chunk * usize::BITS as usize + self.idx_in_chunk as usize
     }
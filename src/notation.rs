@@ -0,0 +1,182 @@
+//! Compact range notation for sets, e.g., `0-5,9,12-20`.
+//!
+//! Elements are listed as comma-separated items, each of which is one of:
+//! - `i`, the single index i,
+//! - `i-j` or `i..=j`, the closed range of indices from i through j,
+//! - `i..j`, the half-open range of indices from i up to (but excluding) j.
+//!
+//! When parsing, whitespace is permitted around items, separators and range operators,
+//! and the empty (or whitespace-only) string denotes the empty set.
+use super::{ChunkRead, Index, IndexSet, Word};
+use core::fmt::{self, Display};
+use core::ops::RangeInclusive;
+use core::str::FromStr;
+
+/// Displays the elements of a ChunkRead in range notation, listing each maximal run as one item.
+/// Single elements are displayed as `i`, longer runs as `i-j`.
+pub struct RangeNotation<'a, A: ChunkRead + ?Sized>(pub(crate) &'a A);
+
+/// Error returned when parsing range notation fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIndexSetError {
+    /// Byte offset in the input at which the problem was found.
+    pub pos: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Expected an index, but found something else (or the end of input).
+    ExpectedIndex,
+    /// Expected a comma or the end of input, but found something else.
+    ExpectedSeparator,
+    /// The index does not fit in an Index.
+    IndexOverflow,
+    /// The range ends before it starts. pos is that of the range's start.
+    DescendingRange,
+    /// The indices are too large to allocate the set. pos is that of the item with the greatest index.
+    TooLarge,
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+///////////////////////////////////////////////////////////////////////
+
+impl<A: ChunkRead + ?Sized> Display for RangeNotation<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, run) in self.0.iter_runs().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            let (start, last) = run.into_inner();
+            if start == last {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, last)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for ParseIndexSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            ParseErrorKind::ExpectedIndex => "expected an index",
+            ParseErrorKind::ExpectedSeparator => "expected ',' or end of input",
+            ParseErrorKind::IndexOverflow => "index is too large",
+            ParseErrorKind::DescendingRange => "range ends before it starts",
+            ParseErrorKind::TooLarge => "index is too large to allocate the set",
+        };
+        write!(f, "{} at byte {}", what, self.pos)
+    }
+}
+impl std::error::Error for ParseIndexSetError {}
+
+impl<'a> Parser<'a> {
+    fn error(&self, pos: usize, kind: ParseErrorKind) -> ParseIndexSetError {
+        ParseIndexSetError { pos, kind }
+    }
+    fn skip_whitespace(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+    /// Consumes the given token if the input continues with it.
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.bytes[self.pos..].starts_with(token.as_bytes());
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+    fn index(&mut self) -> Result<Index, ParseIndexSetError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut index: Index = 0;
+        while let Some(digit) = self.bytes.get(self.pos).filter(|b| b.is_ascii_digit()) {
+            index = index
+                .checked_mul(10)
+                .and_then(|index| index.checked_add((digit - b'0') as Index))
+                .ok_or(self.error(start, ParseErrorKind::IndexOverflow))?;
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error(start, ParseErrorKind::ExpectedIndex));
+        }
+        Ok(index)
+    }
+    /// Parses one item, returning its indices as an inclusive range, or None if it has none, e.g., `4..4`.
+    /// Inclusive, s.t. an item may end at Index::MAX.
+    fn item(&mut self) -> Result<Option<RangeInclusive<Index>>, ParseIndexSetError> {
+        self.skip_whitespace();
+        let item_pos = self.pos;
+        let start = self.index()?;
+        self.skip_whitespace();
+        let descending = self.error(item_pos, ParseErrorKind::DescendingRange);
+        if self.eat("..=") || self.eat("-") {
+            let last = self.index()?;
+            if last < start {
+                return Err(descending);
+            }
+            Ok(Some(start..=last))
+        } else if self.eat("..") {
+            let end = self.index()?;
+            if end < start {
+                return Err(descending);
+            }
+            Ok((start < end).then(|| start..=end - 1))
+        } else {
+            Ok(Some(start..=start))
+        }
+    }
+}
+
+/// Parses a string in range notation into the byte offsets and inclusive ranges of its nonempty items.
+pub(crate) fn parse_ranges(
+    s: &str,
+) -> Result<Vec<(usize, RangeInclusive<Index>)>, ParseIndexSetError> {
+    let mut parser = Parser { bytes: s.as_bytes(), pos: 0 };
+    let mut ranges = vec![];
+    parser.skip_whitespace();
+    if parser.pos == s.len() {
+        return Ok(ranges);
+    }
+    loop {
+        parser.skip_whitespace();
+        let item_pos = parser.pos;
+        if let Some(range) = parser.item()? {
+            ranges.push((item_pos, range));
+        }
+        parser.skip_whitespace();
+        if parser.pos == s.len() {
+            return Ok(ranges);
+        }
+        if !parser.eat(",") {
+            return Err(parser.error(parser.pos, ParseErrorKind::ExpectedSeparator));
+        }
+    }
+}
+
+impl<const N: usize, W: Word> FromStr for IndexSet<N, W> {
+    type Err = ParseIndexSetError;
+    /// Parses a set from range notation. See the notation module for the syntax.
+    fn from_str(s: &str) -> Result<Self, ParseIndexSetError> {
+        let ranges = parse_ranges(s)?;
+        // the text may name indices too large to allocate for, so allocate fallibly
+        let mut set = match ranges.iter().max_by_key(|(_, range)| *range.end()) {
+            Some((pos, range)) => Self::try_with_word_capacity(range.end() / W::BITS as usize + 1)
+                .ok_or(ParseIndexSetError { pos: *pos, kind: ParseErrorKind::TooLarge })?,
+            None => Self::default(),
+        };
+        for (_, range) in ranges {
+            let (start, last) = range.into_inner();
+            set.insert_all_in_range(start..last);
+            set.insert(last);
+        }
+        Ok(set)
+    }
+}
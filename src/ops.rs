@@ -0,0 +1,50 @@
+//! std operator traits, as shorthand for the combinators.
+//! - `&a | &b`, `&a & &b`, `&a ^ &b` and `&a - &b` for IndexSet a eagerly compute a new IndexSet,
+//! - `a |= &b` etc. update IndexSet a in place with overwrite_from_combination,
+//! - `a | &b` etc. for an owned IndexSet a do the same, and return a,
+//! - the same binary operators for combinators a lazily combine a with b, like ChunkRead::or etc.
+//!
+//! In all cases, b may be any ChunkRead, including combinators.
+use super::combinators::bin_ops::{And, Or, Without, Xor};
+use super::{BinChunkOp, ChunkRead, CombinedChunkReads, IndexSet, Word};
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
+
+macro_rules! impl_bin_op {
+    ($Trait:ident, $method:ident, $AssignTrait:ident, $assign_method:ident, $op:ident) => {
+        impl<B: ChunkRead + ?Sized, const N: usize, W: Word> $Trait<&B> for &IndexSet<N, W> {
+            type Output = IndexSet<N, W>;
+            fn $method(self, b: &B) -> IndexSet<N, W> {
+                IndexSet::from_chunk_read(&$op.combine_readers(self, b))
+            }
+        }
+        impl<B: ChunkRead + ?Sized, const N: usize, W: Word> $AssignTrait<&B> for IndexSet<N, W> {
+            fn $assign_method(&mut self, b: &B) {
+                self.overwrite_from_combination($op, b)
+            }
+        }
+        impl<B: ChunkRead + ?Sized, const N: usize, W: Word> $Trait<&B> for IndexSet<N, W> {
+            type Output = IndexSet<N, W>;
+            fn $method(mut self, b: &B) -> IndexSet<N, W> {
+                self.$assign_method(b);
+                self
+            }
+        }
+        impl<'r, 'a, A, B, C, O> $Trait<&'r C> for &'r CombinedChunkReads<'a, A, B, O>
+        where
+            A: ChunkRead + ?Sized,
+            B: ChunkRead + ?Sized,
+            C: ChunkRead + ?Sized,
+            O: BinChunkOp,
+        {
+            type Output = CombinedChunkReads<'r, CombinedChunkReads<'a, A, B, O>, C, $op>;
+            fn $method(self, c: &'r C) -> Self::Output {
+                $op.combine_readers(self, c)
+            }
+        }
+    };
+}
+
+impl_bin_op!(BitOr, bitor, BitOrAssign, bitor_assign, Or);
+impl_bin_op!(BitAnd, bitand, BitAndAssign, bitand_assign, And);
+impl_bin_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, Xor);
+impl_bin_op!(Sub, sub, SubAssign, sub_assign, Without);
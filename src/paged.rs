@@ -0,0 +1,203 @@
+use super::{Chunk, ChunkBitAddr, ChunkRead, Index};
+use core::fmt::Debug;
+use core::iter::FromIterator;
+use core::ops::Range;
+use std::collections::BTreeMap;
+
+/// Number of chunks in each page
+const PAGE_CHUNKS: usize = 64;
+
+type Page = Box<[Chunk; PAGE_CHUNKS]>;
+
+/// Stores a set of indices in pages of PAGE_CHUNKS contiguous chunks, storing only pages with elements.
+/// Supports the same mutations as IndexSet, but memory usage is proportional to the number of nonzero pages,
+/// rather than the largest element, so every index (up to Index::MAX) can be inserted.
+#[derive(Clone, Default)]
+pub struct PagedIndexSet {
+    // invariant: no page is all zeroes
+    pages: BTreeMap<usize, Page>,
+}
+
+impl PagedIndexSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn split_chunk_idx(idx_of_chunk: usize) -> (usize, usize) {
+        (idx_of_chunk / PAGE_CHUNKS, idx_of_chunk % PAGE_CHUNKS)
+    }
+    fn page_is_zero(page: &Page) -> bool {
+        page.iter().all(|&chunk| chunk == 0)
+    }
+    /// Returns the number of pages storing elements.
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+    /// Adds the given index to the set. Returns whether it was absent before i.e. the set has changed.
+    pub fn insert(&mut self, index: Index) -> bool {
+        let cba = ChunkBitAddr::from_bit_idx(index);
+        let (idx_of_page, idx_in_page) = Self::split_chunk_idx(cba.idx_of_chunk);
+        let page = self.pages.entry(idx_of_page).or_insert_with(|| Box::new([0; PAGE_CHUNKS]));
+        let chunk = &mut page[idx_in_page];
+        let was_unset = *chunk & cba.chunk_mask() == 0;
+        *chunk |= cba.chunk_mask();
+        was_unset
+    }
+    /// Removes the given index from the set. Returns whether it was present before i.e. the set has changed.
+    pub fn remove(&mut self, index: Index) -> bool {
+        let cba = ChunkBitAddr::from_bit_idx(index);
+        let (idx_of_page, idx_in_page) = Self::split_chunk_idx(cba.idx_of_chunk);
+        if let Some(page) = self.pages.get_mut(&idx_of_page) {
+            let chunk = &mut page[idx_in_page];
+            let was_set = *chunk & cba.chunk_mask() != 0;
+            *chunk &= !cba.chunk_mask();
+            if *chunk == 0 && Self::page_is_zero(page) {
+                self.pages.remove(&idx_of_page);
+            }
+            was_set
+        } else {
+            false
+        }
+    }
+    /// Afterwards, contains will return the given value. Returns true iff a change occurred.
+    pub fn make_contains(&mut self, index: Index, value: bool) -> bool {
+        if value {
+            self.insert(index)
+        } else {
+            self.remove(index)
+        }
+    }
+    /// Adds all indices in the given range to the set.
+    pub fn insert_all_in_range(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let first = ChunkBitAddr::from_bit_idx(range.start);
+        let last = ChunkBitAddr::from_bit_idx(range.end - 1);
+        let first_page = first.idx_of_chunk / PAGE_CHUNKS;
+        let last_page = last.idx_of_chunk / PAGE_CHUNKS;
+        for idx_of_page in first_page..=last_page {
+            let page = self.pages.entry(idx_of_page).or_insert_with(|| Box::new([0; PAGE_CHUNKS]));
+            // the chunks of this page in the range. only the outermost may be partially in it
+            let page_start = idx_of_page * PAGE_CHUNKS;
+            let lo = first.idx_of_chunk.max(page_start) - page_start;
+            let hi = last.idx_of_chunk.min(page_start + PAGE_CHUNKS - 1) - page_start;
+            if lo + 1 < hi {
+                page[lo + 1..hi].fill(!0);
+            }
+            for idx_in_page in [lo, hi] {
+                let idx_of_chunk = page_start + idx_in_page;
+                page[idx_in_page] |= ChunkBitAddr::chunk_mask_between(first, last, idx_of_chunk);
+            }
+        }
+    }
+    /// Removes all indices in the given range from the set.
+    pub fn remove_all_in_range(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let first = ChunkBitAddr::from_bit_idx(range.start);
+        let last = ChunkBitAddr::from_bit_idx(range.end - 1);
+        let first_page = first.idx_of_chunk / PAGE_CHUNKS;
+        let last_page = last.idx_of_chunk / PAGE_CHUNKS;
+        let mut emptied = vec![];
+        for (&idx_of_page, page) in self.pages.range_mut(first_page..=last_page) {
+            for (idx_in_page, chunk) in page.iter_mut().enumerate() {
+                let idx_of_chunk = idx_of_page * PAGE_CHUNKS + idx_in_page;
+                if (first.idx_of_chunk..=last.idx_of_chunk).contains(&idx_of_chunk) {
+                    *chunk &= !ChunkBitAddr::chunk_mask_between(first, last, idx_of_chunk);
+                }
+            }
+            if Self::page_is_zero(page) {
+                emptied.push(idx_of_page);
+            }
+        }
+        for idx_of_page in emptied {
+            self.pages.remove(&idx_of_page);
+        }
+    }
+    /// Equivalent to for i in r.iter() { self.insert(i); }
+    pub fn insert_all<R: ChunkRead + ?Sized>(&mut self, r: &R) {
+        for (idx_of_chunk, read_chunk) in r.iter_nonzero_chunks() {
+            let (idx_of_page, idx_in_page) = Self::split_chunk_idx(idx_of_chunk);
+            let page = self.pages.entry(idx_of_page).or_insert_with(|| Box::new([0; PAGE_CHUNKS]));
+            page[idx_in_page] |= read_chunk;
+        }
+    }
+    /// Equivalent to for i in r.iter() { self.remove(i); }
+    pub fn remove_all<R: ChunkRead + ?Sized>(&mut self, r: &R) {
+        for (idx_of_chunk, read_chunk) in r.iter_nonzero_chunks() {
+            let (idx_of_page, idx_in_page) = Self::split_chunk_idx(idx_of_chunk);
+            if let Some(page) = self.pages.get_mut(&idx_of_page) {
+                page[idx_in_page] &= !read_chunk;
+                if Self::page_is_zero(page) {
+                    self.pages.remove(&idx_of_page);
+                }
+            }
+        }
+    }
+    /// Removes all elements, freeing all pages.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+}
+
+impl ChunkRead for PagedIndexSet {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        let (idx_of_page, idx_in_page) = Self::split_chunk_idx(idx_of_chunk);
+        match self.pages.get(&idx_of_page) {
+            Some(page) => Some(page[idx_in_page]),
+            // missing page before the last page
+            None if self.pages.keys().next_back().is_some_and(|&last| idx_of_page < last) => {
+                Some(0)
+            }
+            None => None,
+        }
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        self.pages.keys().next_back().map(|&last| (last + 1) * PAGE_CHUNKS).unwrap_or(0)
+    }
+    fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        let (first_page, first_idx_in_page) = Self::split_chunk_idx(idx_of_chunk);
+        for (&idx_of_page, page) in self.pages.range(first_page..) {
+            let from = if idx_of_page == first_page { first_idx_in_page } else { 0 };
+            if let Some(position) = page[from..].iter().position(|&chunk| chunk != 0) {
+                let idx_in_page = from + position;
+                return Some((idx_of_page * PAGE_CHUNKS + idx_in_page, page[idx_in_page]));
+            }
+        }
+        None
+    }
+    fn len(&self) -> usize {
+        let chunks = self.pages.values().flat_map(|page| page.iter());
+        chunks.map(|chunk| chunk.count_ones() as usize).sum()
+    }
+    fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+}
+
+impl Debug for PagedIndexSet {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+impl PartialEq for PagedIndexSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.pages == other.pages
+    }
+}
+impl Eq for PagedIndexSet {}
+impl core::hash::Hash for PagedIndexSet {
+    fn hash<H: core::hash::Hasher>(&self, hasher: &mut H) {
+        self.pages.hash(hasher)
+    }
+}
+impl FromIterator<Index> for PagedIndexSet {
+    fn from_iter<I: IntoIterator<Item = Index>>(into_iter: I) -> Self {
+        let mut m = Self::default();
+        for index in into_iter.into_iter() {
+            m.insert(index);
+        }
+        m
+    }
+}
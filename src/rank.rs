@@ -0,0 +1,113 @@
+use super::{select_in_chunk, Chunk, ChunkBitAddr, ChunkRead, Index};
+
+/// Chunks per block of the directory. Rank and select scan at most this many chunks.
+const BLOCK_CHUNKS: usize = 8;
+
+/// Directory of cumulative element counts of some ChunkRead, one per block of BLOCK_CHUNKS chunks,
+/// s.t. rank takes constant time and select takes logarithmic time.
+/// The directory does not borrow the reader it was built from.
+/// Its queries must be given the same reader, and it must be rebuilt whenever that reader changes.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RankDirectory {
+    // block_ranks[b] is the number of elements in chunks before block b.
+    // The last entry is the total number of elements.
+    block_ranks: Vec<usize>,
+}
+
+/// A ChunkRead with an attached RankDirectory, overriding rank and select with faster versions.
+#[derive(Debug, Clone)]
+pub struct Ranked<'a, A: ChunkRead + ?Sized> {
+    a: &'a A,
+    directory: RankDirectory,
+}
+
+impl RankDirectory {
+    pub fn new<A: ChunkRead + ?Sized>(a: &A) -> Self {
+        let mut me = Self::default();
+        me.rebuild(a);
+        me
+    }
+    /// Recomputes the directory for the given reader, reusing the allocation.
+    pub fn rebuild<A: ChunkRead + ?Sized>(&mut self, a: &A) {
+        let blocks = a.zero_chunks_from_exact().div_ceil(BLOCK_CHUNKS);
+        self.block_ranks.clear();
+        self.block_ranks.resize(blocks + 1, 0);
+        for (idx_of_chunk, chunk) in a.iter_nonzero_chunks() {
+            self.block_ranks[idx_of_chunk / BLOCK_CHUNKS + 1] += chunk.count_ones() as usize;
+        }
+        for b in 1..self.block_ranks.len() {
+            self.block_ranks[b] += self.block_ranks[b - 1];
+        }
+    }
+    /// Returns the number of elements in the reader the directory was built from.
+    pub fn len(&self) -> usize {
+        self.block_ranks.last().copied().unwrap_or(0)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Equivalent to a.rank(bit_idx), given that self was (re)built from a.
+    pub fn rank<A: ChunkRead + ?Sized>(&self, a: &A, bit_idx: Index) -> usize {
+        let cba = ChunkBitAddr::from_bit_idx(bit_idx);
+        let block = cba.idx_of_chunk / BLOCK_CHUNKS;
+        if self.block_ranks.len() <= block + 1 {
+            // bit_idx is beyond the last nonzero chunk
+            return self.len();
+        }
+        let mut rank = self.block_ranks[block];
+        for idx_of_chunk in block * BLOCK_CHUNKS..cba.idx_of_chunk {
+            rank += a.get_chunk(idx_of_chunk).unwrap_or(0).count_ones() as usize;
+        }
+        let chunk: Chunk = a.get_chunk(cba.idx_of_chunk).unwrap_or(0);
+        rank + (chunk & !cba.chunk_mask_from()).count_ones() as usize
+    }
+    /// Equivalent to a.select(k), given that self was (re)built from a.
+    pub fn select<A: ChunkRead + ?Sized>(&self, a: &A, mut k: usize) -> Option<Index> {
+        if self.len() <= k {
+            return None;
+        }
+        // the last block whose preceding blocks have <= k elements contains the answer
+        let block = self.block_ranks.partition_point(|&rank| rank <= k) - 1;
+        k -= self.block_ranks[block];
+        for idx_of_chunk in block * BLOCK_CHUNKS.. {
+            let chunk = a.get_chunk(idx_of_chunk)?;
+            let ones = chunk.count_ones() as usize;
+            if k < ones {
+                let idx_in_chunk = select_in_chunk(chunk, k);
+                return Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx());
+            }
+            k -= ones;
+        }
+        None
+    }
+}
+
+impl<'a, A: ChunkRead + ?Sized> Ranked<'a, A> {
+    pub fn new(a: &'a A) -> Self {
+        Self { a, directory: RankDirectory::new(a) }
+    }
+    pub fn directory(&self) -> &RankDirectory {
+        &self.directory
+    }
+}
+
+impl<A: ChunkRead + ?Sized> ChunkRead for Ranked<'_, A> {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        self.a.get_chunk(idx_of_chunk)
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        self.a.zero_chunks_from_conservative()
+    }
+    fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        self.a.next_nonzero_chunk_from(idx_of_chunk)
+    }
+    fn len(&self) -> usize {
+        self.directory.len()
+    }
+    fn rank(&self, bit_idx: Index) -> usize {
+        self.directory.rank(self.a, bit_idx)
+    }
+    fn select(&self, k: usize) -> Option<Index> {
+        self.directory.select(self.a, k)
+    }
+}
@@ -0,0 +1,514 @@
+use super::{chunk_mask_of_bits, Chunk, ChunkBitAddr, ChunkRead, Index};
+use core::fmt::Debug;
+use core::iter::FromIterator;
+use core::ops::Range;
+
+/// Number of indices in each block
+const BLOCK_BITS: usize = 1 << 16;
+/// Number of chunks in each block
+const BLOCK_CHUNKS: usize = BLOCK_BITS / usize::BITS as usize;
+/// Array containers with more elements are converted to bitmaps.
+const ARRAY_MAX_LEN: usize = 4096;
+/// Run containers with more runs are converted to bitmaps.
+const RUNS_MAX_LEN: usize = 2048;
+
+/// Stores the elements of one block, as offsets from the block's first index.
+#[derive(Debug, Clone)]
+enum Container {
+    /// sorted, distinct offsets. Efficient for sparse blocks.
+    Array(Vec<u16>),
+    /// one bit per offset. Efficient for dense blocks.
+    Bitmap { chunks: Box<[Chunk; BLOCK_CHUNKS]>, len: usize },
+    /// sorted, disjoint, non-adjacent inclusive (first, last) offset ranges. Efficient for blocks of long runs.
+    Runs(Vec<(u16, u16)>),
+}
+
+/// Stores a set of indices like a roaring bitmap: the index space is split into blocks of 2^16 indices,
+/// and only blocks with elements are stored, each as a sorted array, bitmap, or list of runs,
+/// depending on which is smallest. Unlike IndexSet, memory usage is independent of the magnitude of the indices.
+#[derive(Clone, Default)]
+pub struct RoaringIndexSet {
+    // invariants: sorted by block index (the index of its first element / BLOCK_BITS).
+    // no container is empty.
+    blocks: Vec<(usize, Container)>,
+}
+
+///////////////////////////////////////////////////////////////////////
+
+fn split_index(index: Index) -> (usize, u16) {
+    (index / BLOCK_BITS, (index % BLOCK_BITS) as u16)
+}
+
+/// Sets or clears the bits for offsets first..=last
+fn bitmap_fill(chunks: &mut [Chunk; BLOCK_CHUNKS], first: u16, last: u16, value: bool) {
+    let first = ChunkBitAddr::from_bit_idx(first as usize);
+    let last = ChunkBitAddr::from_bit_idx(last as usize);
+    let touched =
+        chunks.iter_mut().enumerate().take(last.idx_of_chunk + 1).skip(first.idx_of_chunk);
+    for (idx_of_chunk, chunk) in touched {
+        let mut mask: Chunk = !0;
+        if idx_of_chunk == first.idx_of_chunk {
+            mask &= first.chunk_mask_from();
+        }
+        if idx_of_chunk == last.idx_of_chunk {
+            mask &= last.chunk_mask_through();
+        }
+        if value {
+            *chunk |= mask;
+        } else {
+            *chunk &= !mask;
+        }
+    }
+}
+
+/// Yields the offsets of the bits of the chunk at the given chunk index (within its block), in ascending order.
+fn chunk_offsets(idx_of_chunk: usize, mut chunk: Chunk) -> impl Iterator<Item = u16> {
+    core::iter::from_fn(move || {
+        let idx_in_chunk = (chunk != 0).then(|| chunk.trailing_zeros())?;
+        chunk &= chunk - 1;
+        Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx() as u16)
+    })
+}
+
+/// Yields the maximal runs of the chunk at the given chunk index (within its block)
+/// as inclusive (first, last) offset ranges, in ascending order.
+fn chunk_runs(idx_of_chunk: usize, mut chunk: Chunk) -> impl Iterator<Item = (u16, u16)> {
+    let lo = idx_of_chunk * usize::BITS as usize;
+    core::iter::from_fn(move || {
+        let first = (chunk != 0).then(|| chunk.trailing_zeros())?;
+        let last = first + (chunk >> first).trailing_ones() - 1;
+        chunk &= !chunk_mask_of_bits(first..last + 1);
+        Some(((lo + first as usize) as u16, (lo + last as usize) as u16))
+    })
+}
+
+/// Adds the run first..=last to runs, merging it with any runs it overlaps or touches.
+fn runs_insert_range(runs: &mut Vec<(u16, u16)>, first: u16, last: u16) {
+    let start = runs.partition_point(|&(_, l)| (l as u32) + 1 < first as u32);
+    let end = runs.partition_point(|&(f, _)| f as u32 <= last as u32 + 1);
+    let (mut first, mut last) = (first, last);
+    if start < end {
+        first = first.min(runs[start].0);
+        last = last.max(runs[end - 1].1);
+    }
+    runs.splice(start..end, [(first, last)]);
+}
+
+/// Removes first..=last from runs, splitting any runs it partially overlaps.
+fn runs_remove_range(runs: &mut Vec<(u16, u16)>, first: u16, last: u16) {
+    let start = runs.partition_point(|&(_, l)| l < first);
+    let end = runs.partition_point(|&(f, _)| f <= last);
+    if start < end {
+        let (first_kept, last_kept) = (runs[start].0, runs[end - 1].1);
+        let mut kept = Vec::with_capacity(2);
+        if first_kept < first {
+            kept.push((first_kept, first - 1));
+        }
+        if last < last_kept {
+            kept.push((last + 1, last_kept));
+        }
+        runs.splice(start..end, kept);
+    }
+}
+
+impl Container {
+    fn len(&self) -> usize {
+        match self {
+            Container::Array(array) => array.len(),
+            Container::Bitmap { len, .. } => *len,
+            Container::Runs(runs) => runs.iter().map(|&(f, l)| (l - f) as usize + 1).sum(),
+        }
+    }
+    fn is_empty(&self) -> bool {
+        match self {
+            Container::Array(array) => array.is_empty(),
+            Container::Bitmap { len, .. } => *len == 0,
+            Container::Runs(runs) => runs.is_empty(),
+        }
+    }
+    fn contains(&self, offset: u16) -> bool {
+        match self {
+            Container::Array(array) => array.binary_search(&offset).is_ok(),
+            Container::Bitmap { chunks, .. } => {
+                let cba = ChunkBitAddr::from_bit_idx(offset as usize);
+                chunks[cba.idx_of_chunk] & cba.chunk_mask() != 0
+            }
+            Container::Runs(runs) => {
+                let i = runs.partition_point(|&(f, _)| f <= offset);
+                i > 0 && offset <= runs[i - 1].1
+            }
+        }
+    }
+    fn insert(&mut self, offset: u16) -> bool {
+        let inserted = match self {
+            Container::Array(array) => match array.binary_search(&offset) {
+                Ok(_) => false,
+                Err(i) => {
+                    array.insert(i, offset);
+                    true
+                }
+            },
+            Container::Bitmap { chunks, len } => {
+                let cba = ChunkBitAddr::from_bit_idx(offset as usize);
+                let was_unset = chunks[cba.idx_of_chunk] & cba.chunk_mask() == 0;
+                chunks[cba.idx_of_chunk] |= cba.chunk_mask();
+                *len += was_unset as usize;
+                was_unset
+            }
+            Container::Runs(runs) => {
+                let i = runs.partition_point(|&(f, _)| f <= offset);
+                let was_unset = i == 0 || runs[i - 1].1 < offset;
+                if was_unset {
+                    runs_insert_range(runs, offset, offset);
+                }
+                was_unset
+            }
+        };
+        self.fit();
+        inserted
+    }
+    fn remove(&mut self, offset: u16) -> bool {
+        let removed = self.contains(offset);
+        if removed {
+            self.remove_range(offset, offset);
+        }
+        removed
+    }
+    fn insert_range(&mut self, first: u16, last: u16) {
+        match self {
+            Container::Runs(runs) => runs_insert_range(runs, first, last),
+            Container::Array(_) => {
+                *self = self.to_bitmap();
+                self.insert_range(first, last);
+            }
+            Container::Bitmap { chunks, len } => {
+                bitmap_fill(chunks, first, last, true);
+                *len = chunks.iter().map(|chunk| chunk.count_ones() as usize).sum();
+            }
+        }
+        self.fit();
+    }
+    fn remove_range(&mut self, first: u16, last: u16) {
+        match self {
+            Container::Runs(runs) => runs_remove_range(runs, first, last),
+            Container::Array(array) => array.retain(|&offset| offset < first || last < offset),
+            Container::Bitmap { chunks, len } => {
+                bitmap_fill(chunks, first, last, false);
+                *len = chunks.iter().map(|chunk| chunk.count_ones() as usize).sum();
+            }
+        }
+        self.fit();
+    }
+    /// ORs the given chunk into the container at the given chunk index (within this block).
+    /// Merges the whole chunk at once, rather than inserting its bits one by one.
+    fn insert_chunk(&mut self, idx_of_chunk: usize, chunk: Chunk) {
+        let lo = idx_of_chunk * usize::BITS as usize;
+        let hi = lo + usize::BITS as usize;
+        match self {
+            Container::Bitmap { chunks, len } => {
+                *len += (chunk & !chunks[idx_of_chunk]).count_ones() as usize;
+                chunks[idx_of_chunk] |= chunk;
+            }
+            Container::Array(array)
+                if ARRAY_MAX_LEN < array.len() + chunk.count_ones() as usize =>
+            {
+                // too dense for an array anyway
+                *self = self.to_bitmap();
+                self.insert_chunk(idx_of_chunk, chunk);
+            }
+            Container::Array(array) => {
+                // the array's offsets within this chunk are contiguous. replace them in one splice
+                let start = array.partition_point(|&offset| (offset as usize) < lo);
+                let end = array.partition_point(|&offset| (offset as usize) < hi);
+                let merged = array[start..end]
+                    .iter()
+                    .fold(chunk, |c, &offset| c | 1 << (offset as usize - lo));
+                array.splice(start..end, chunk_offsets(idx_of_chunk, merged));
+            }
+            Container::Runs(runs) => {
+                // the runs overlapping or touching this chunk are contiguous. replace them in one splice
+                let start = runs.partition_point(|&(_, l)| (l as usize) + 1 < lo);
+                let end = runs.partition_point(|&(f, _)| f as usize <= hi);
+                let mut sorted: Vec<(u16, u16)> = runs[start..end]
+                    .iter()
+                    .copied()
+                    .chain(chunk_runs(idx_of_chunk, chunk))
+                    .collect();
+                sorted.sort_unstable();
+                // coalesce the runs which overlap or touch
+                let mut merged: Vec<(u16, u16)> = Vec::with_capacity(sorted.len());
+                for (f, l) in sorted {
+                    match merged.last_mut() {
+                        Some((_, last)) if f as u32 <= *last as u32 + 1 => *last = (*last).max(l),
+                        _ => merged.push((f, l)),
+                    }
+                }
+                runs.splice(start..end, merged);
+            }
+        }
+        self.fit();
+    }
+    /// Returns the chunk at the given chunk index (within this block)
+    fn get_chunk(&self, idx_of_chunk: usize) -> Chunk {
+        let lo = (idx_of_chunk * usize::BITS as usize) as u32;
+        let hi = lo + usize::BITS;
+        match self {
+            Container::Array(array) => {
+                let start = array.partition_point(|&offset| (offset as u32) < lo);
+                array[start..]
+                    .iter()
+                    .take_while(|&&offset| (offset as u32) < hi)
+                    .fold(0, |chunk, &offset| chunk | 1 << (offset as u32 - lo))
+            }
+            Container::Bitmap { chunks, .. } => chunks[idx_of_chunk],
+            Container::Runs(runs) => {
+                let start = runs.partition_point(|&(_, l)| (l as u32) < lo);
+                runs[start..].iter().take_while(|&&(f, _)| (f as u32) < hi).fold(
+                    0,
+                    |chunk, &(f, l)| {
+                        let bits = (f as u32).max(lo) - lo..(l as u32 + 1).min(hi) - lo;
+                        chunk | chunk_mask_of_bits(bits)
+                    },
+                )
+            }
+        }
+    }
+    /// Returns the index of the first nonzero chunk at or after idx_of_chunk (within this block)
+    fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<usize> {
+        let lo = idx_of_chunk * usize::BITS as usize;
+        let offset = match self {
+            Container::Array(array) => {
+                *array.get(array.partition_point(|&offset| (offset as usize) < lo))? as usize
+            }
+            Container::Bitmap { chunks, .. } => {
+                let position = chunks[idx_of_chunk..].iter().position(|&chunk| chunk != 0)?;
+                return Some(idx_of_chunk + position);
+            }
+            Container::Runs(runs) => {
+                let &(f, _) = runs.get(runs.partition_point(|&(_, l)| (l as usize) < lo))?;
+                (f as usize).max(lo)
+            }
+        };
+        Some(offset / usize::BITS as usize)
+    }
+    fn to_array(&self) -> Vec<u16> {
+        match self {
+            Container::Array(array) => array.clone(),
+            Container::Bitmap { .. } | Container::Runs(_) => {
+                let mut array = Vec::with_capacity(self.len());
+                for idx_of_chunk in 0..BLOCK_CHUNKS {
+                    let mut chunk = self.get_chunk(idx_of_chunk);
+                    while chunk != 0 {
+                        let idx_in_chunk = chunk.trailing_zeros();
+                        chunk &= chunk - 1;
+                        array.push(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx() as u16);
+                    }
+                }
+                array
+            }
+        }
+    }
+    fn to_bitmap(&self) -> Self {
+        let mut chunks = Box::new([0; BLOCK_CHUNKS]);
+        for (idx_of_chunk, chunk) in chunks.iter_mut().enumerate() {
+            *chunk = self.get_chunk(idx_of_chunk);
+        }
+        Container::Bitmap { chunks, len: self.len() }
+    }
+    fn to_runs(&self) -> Vec<(u16, u16)> {
+        match self {
+            Container::Runs(runs) => runs.clone(),
+            Container::Array(_) | Container::Bitmap { .. } => {
+                let mut runs: Vec<(u16, u16)> = vec![];
+                for offset in self.to_array() {
+                    match runs.last_mut() {
+                        Some((_, l)) if *l as u32 + 1 == offset as u32 => *l = offset,
+                        _ => runs.push((offset, offset)),
+                    }
+                }
+                runs
+            }
+        }
+    }
+    /// Switches representation if this one has become too large.
+    /// Bitmaps become arrays only once they are half as small as the largest array, avoiding thrashing.
+    fn fit(&mut self) {
+        match self {
+            Container::Array(array) if ARRAY_MAX_LEN < array.len() => *self = self.to_bitmap(),
+            Container::Runs(runs) if RUNS_MAX_LEN < runs.len() => *self = self.to_bitmap(),
+            Container::Bitmap { len, .. } if *len < ARRAY_MAX_LEN / 2 => {
+                *self = Container::Array(self.to_array())
+            }
+            _ => {}
+        }
+    }
+    /// Switches to whichever representation takes the fewest bytes.
+    fn optimize(&mut self) {
+        let runs = self.to_runs();
+        let len = self.len();
+        let array_bytes = 2 * len;
+        let runs_bytes = 4 * runs.len();
+        let bitmap_bytes = BLOCK_BITS / 8;
+        if runs_bytes <= array_bytes.min(bitmap_bytes) {
+            *self = Container::Runs(runs);
+        } else if array_bytes <= bitmap_bytes {
+            *self = Container::Array(self.to_array());
+        } else if !matches!(self, Container::Bitmap { .. }) {
+            *self = self.to_bitmap();
+        }
+    }
+}
+
+impl RoaringIndexSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates a RoaringIndexSet with the same elements as the given reader.
+    pub fn from_chunk_read<R: ChunkRead + ?Sized>(r: &R) -> Self {
+        let mut me = Self::new();
+        me.insert_all(r);
+        me.optimize();
+        me
+    }
+    /// Returns the position of the container for the given block in self.blocks, or where it would be inserted.
+    fn find_block(&self, block: usize) -> Result<usize, usize> {
+        self.blocks.binary_search_by_key(&block, |&(b, _)| b)
+    }
+    /// Returns the container for the given block, creating an empty one if necessary.
+    /// The caller must ensure it does not remain empty.
+    fn container_mut(&mut self, block: usize) -> &mut Container {
+        let i = match self.find_block(block) {
+            Ok(i) => i,
+            Err(i) => {
+                self.blocks.insert(i, (block, Container::Array(vec![])));
+                i
+            }
+        };
+        &mut self.blocks[i].1
+    }
+    /// Adds the given index to the set. Returns whether it was absent before i.e. the set has changed.
+    pub fn insert(&mut self, index: Index) -> bool {
+        let (block, offset) = split_index(index);
+        self.container_mut(block).insert(offset)
+    }
+    /// Removes the given index from the set. Returns whether it was present before i.e. the set has changed.
+    pub fn remove(&mut self, index: Index) -> bool {
+        let (block, offset) = split_index(index);
+        if let Ok(i) = self.find_block(block) {
+            let removed = self.blocks[i].1.remove(offset);
+            if self.blocks[i].1.is_empty() {
+                self.blocks.remove(i);
+            }
+            removed
+        } else {
+            false
+        }
+    }
+    /// Adds all indices in the given range to the set.
+    pub fn insert_all_in_range(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let (first_block, first_offset) = split_index(range.start);
+        let (last_block, last_offset) = split_index(range.end - 1);
+        for block in first_block..=last_block {
+            let first = if block == first_block { first_offset } else { 0 };
+            let last = if block == last_block { last_offset } else { u16::MAX };
+            match self.find_block(block) {
+                Ok(i) => self.blocks[i].1.insert_range(first, last),
+                Err(i) => self.blocks.insert(i, (block, Container::Runs(vec![(first, last)]))),
+            }
+        }
+    }
+    /// Removes all indices in the given range from the set.
+    pub fn remove_all_in_range(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let (first_block, first_offset) = split_index(range.start);
+        let (last_block, last_offset) = split_index(range.end - 1);
+        self.blocks.retain_mut(|(block, container)| {
+            if (first_block..=last_block).contains(block) {
+                let first = if *block == first_block { first_offset } else { 0 };
+                let last = if *block == last_block { last_offset } else { u16::MAX };
+                container.remove_range(first, last);
+            }
+            !container.is_empty()
+        });
+    }
+    /// Equivalent to for i in r.iter() { self.insert(i); }
+    pub fn insert_all<R: ChunkRead + ?Sized>(&mut self, r: &R) {
+        for (idx_of_chunk, chunk) in r.iter_nonzero_chunks() {
+            let block = idx_of_chunk / BLOCK_CHUNKS;
+            self.container_mut(block).insert_chunk(idx_of_chunk % BLOCK_CHUNKS, chunk);
+        }
+    }
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+    /// Converts every block to its most compact representation. Useful after many insertions or removals,
+    /// particularly for detecting blocks best represented by runs.
+    pub fn optimize(&mut self) {
+        for (_, container) in self.blocks.iter_mut() {
+            container.optimize();
+        }
+    }
+}
+
+impl ChunkRead for RoaringIndexSet {
+    fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+        match self.find_block(idx_of_chunk / BLOCK_CHUNKS) {
+            Ok(i) => Some(self.blocks[i].1.get_chunk(idx_of_chunk % BLOCK_CHUNKS)),
+            // missing block between others
+            Err(i) if i < self.blocks.len() => Some(0),
+            Err(_) => None,
+        }
+    }
+    fn zero_chunks_from_conservative(&self) -> usize {
+        self.blocks.last().map(|&(block, _)| (block + 1) * BLOCK_CHUNKS).unwrap_or(0)
+    }
+    fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        let block = idx_of_chunk / BLOCK_CHUNKS;
+        let start = self.blocks.partition_point(|&(b, _)| b < block);
+        for (b, container) in self.blocks[start..].iter() {
+            let from = if *b == block { idx_of_chunk % BLOCK_CHUNKS } else { 0 };
+            if let Some(idx_in_block) = container.next_nonzero_chunk_from(from) {
+                let chunk = container.get_chunk(idx_in_block);
+                return Some((b * BLOCK_CHUNKS + idx_in_block, chunk));
+            }
+        }
+        None
+    }
+    fn contains(&self, index: Index) -> bool {
+        let (block, offset) = split_index(index);
+        self.find_block(block).map(|i| self.blocks[i].1.contains(offset)).unwrap_or(false)
+    }
+    fn len(&self) -> usize {
+        self.blocks.iter().map(|(_, container)| container.len()).sum()
+    }
+    fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+impl Debug for RoaringIndexSet {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+impl PartialEq for RoaringIndexSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter_nonzero_chunks().eq(other.iter_nonzero_chunks())
+    }
+}
+impl Eq for RoaringIndexSet {}
+impl FromIterator<Index> for RoaringIndexSet {
+    fn from_iter<I: IntoIterator<Item = Index>>(into_iter: I) -> Self {
+        let mut m = Self::default();
+        for index in into_iter.into_iter() {
+            m.insert(index);
+        }
+        m
+    }
+}
@@ -0,0 +1,95 @@
+use super::{
+    chunks_from_u64_words, u64_word, u64_word_count, Chunk, ChunkRead, Index, IndexSet, Word,
+};
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// Serializes any ChunkRead, in the same format as IndexSet.
+/// Human-readable formats get a sorted sequence of indices.
+/// Other formats get a compact sequence of little-endian u64 words, omitting trailing zeroes.
+pub struct SerializableIndexSet<'a, A: ChunkRead + ?Sized>(pub(crate) &'a A);
+
+impl<A: ChunkRead + ?Sized> Serialize for SerializableIndexSet<'_, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+            for index in self.0.iter() {
+                seq.serialize_element(&index)?;
+            }
+            seq.end()
+        } else {
+            let word_count = u64_word_count(self.0);
+            let mut seq = serializer.serialize_seq(Some(word_count))?;
+            for idx_of_word in 0..word_count {
+                seq.serialize_element(&u64_word(self.0, idx_of_word))?;
+            }
+            seq.end()
+        }
+    }
+}
+
+impl<const N: usize, W: Word> Serialize for IndexSet<N, W> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serializable().serialize(serializer)
+    }
+}
+
+struct IndicesVisitor<const N: usize, W>(PhantomData<W>);
+struct WordsVisitor<const N: usize, W>(PhantomData<W>);
+
+impl<'de, const N: usize, W: Word> Visitor<'de> for IndicesVisitor<N, W> {
+    type Value = IndexSet<N, W>;
+    fn expecting(&self, f: &mut Formatter) -> core::fmt::Result {
+        f.write_str("a sequence of indices")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<IndexSet<N, W>, A::Error> {
+        // grows as indices are actually read, so a corrupt length cannot cause a huge allocation
+        let mut indices: Vec<Index> = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1 << 12));
+        while let Some(index) = seq.next_element()? {
+            indices.push(index);
+        }
+        // a single huge index may need more memory than is available, so allocate fallibly
+        let chunk_count = indices.iter().max().map_or(0, |&max| max / W::BITS as usize + 1);
+        let mut set = IndexSet::try_with_word_capacity(chunk_count).ok_or_else(|| {
+            A::Error::custom(format_args!("cannot allocate an IndexSet of {} chunks", chunk_count))
+        })?;
+        for index in indices {
+            set.insert(index);
+        }
+        Ok(set)
+    }
+}
+
+impl<'de, const N: usize, W: Word> Visitor<'de> for WordsVisitor<N, W> {
+    type Value = IndexSet<N, W>;
+    fn expecting(&self, f: &mut Formatter) -> core::fmt::Result {
+        f.write_str("a sequence of u64 words")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<IndexSet<N, W>, A::Error> {
+        let mut words: Vec<u64> = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1 << 12));
+        while let Some(word) = seq.next_element()? {
+            words.push(word);
+        }
+        // trailing zero words were omitted when serializing, but may be present anyway
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        let chunks: Vec<Chunk> = chunks_from_u64_words(&words).collect();
+        Ok(IndexSet::from_chunk_read(&chunks[..]))
+    }
+}
+
+impl<'de, const N: usize, W: Word> Deserialize<'de> for IndexSet<N, W> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(IndicesVisitor::<N, W>(PhantomData))
+        } else {
+            deserializer.deserialize_seq(WordsVisitor::<N, W>(PhantomData))
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use super::{Chunk, ChunkRead, IndexSet};
+use core::ops::{AddAssign, SubAssign};
+
+/// Dense map from the subsets of the universe of indices 0..universe_size to values,
+/// e.g., a dynamic programming table over subsets.
+/// Stores one value per subset in a flat vector, indexed by the subset's (only) chunk,
+/// so universe_size must be less than usize::BITS, and memory usage is exponential in it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SubsetMap<V> {
+    universe_size: usize,
+    // values[chunk] is the value of the subset with chunk as its first and only chunk
+    values: Vec<V>,
+}
+
+impl<V> SubsetMap<V> {
+    /// Panics if universe_size >= usize::BITS
+    pub fn from_fn<F: FnMut(IndexSet<1>) -> V>(universe_size: usize, mut f: F) -> Self {
+        assert!(universe_size < usize::BITS as usize, "universe of SubsetMap is too large");
+        let values =
+            (0..1 << universe_size).map(|chunk| f(IndexSet::from_chunk_slice(&[chunk]))).collect();
+        Self { universe_size, values }
+    }
+    /// Panics if universe_size >= usize::BITS
+    pub fn filled(universe_size: usize, value: V) -> Self
+    where
+        V: Clone,
+    {
+        Self::from_fn(universe_size, |_| value.clone())
+    }
+    pub fn universe_size(&self) -> usize {
+        self.universe_size
+    }
+    /// Returns the number of entries, i.e., 2^universe_size.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        // the empty set is always a key
+        false
+    }
+    /// Returns the position of the given key's value, or None if the key has elements outside the universe.
+    fn position<K: ChunkRead + ?Sized>(&self, key: &K) -> Option<Chunk> {
+        let chunk = key.get_chunk(0).unwrap_or(0);
+        let in_universe =
+            chunk >> self.universe_size == 0 && key.next_nonzero_chunk_from(1).is_none();
+        in_universe.then_some(chunk)
+    }
+    /// Returns None if the key has elements outside the universe.
+    pub fn get<K: ChunkRead + ?Sized>(&self, key: &K) -> Option<&V> {
+        Some(&self.values[self.position(key)?])
+    }
+    /// Returns None if the key has elements outside the universe.
+    pub fn get_mut<K: ChunkRead + ?Sized>(&mut self, key: &K) -> Option<&mut V> {
+        let position = self.position(key)?;
+        Some(&mut self.values[position])
+    }
+    /// Iterates over (key, value) entries in ascending powerset order, i.e., starting with the empty set.
+    /// This is the reverse of the order of IndexSet::try_decrease_in_powerset_order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (IndexSet<1>, &V)> + '_ {
+        self.values.iter().enumerate().map(|(chunk, v)| (IndexSet::from_chunk_slice(&[chunk]), v))
+    }
+    /// Like iter, but with mutable values.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = (IndexSet<1>, &mut V)> + '_ {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .map(|(chunk, v)| (IndexSet::from_chunk_slice(&[chunk]), v))
+    }
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+    /// Replaces the value of each set S with the sum of the values of all subsets of S.
+    /// Takes O(universe_size * len()) additions.
+    pub fn zeta_transform(&mut self)
+    where
+        V: for<'v> AddAssign<&'v V>,
+    {
+        self.for_each_subset_pair(|sub, sup| *sup += sub)
+    }
+    /// Inverse of zeta_transform: replaces the value of each set S with the value v s.t.
+    /// the original value of S is the sum of v over all subsets of S.
+    /// Takes O(universe_size * len()) subtractions.
+    pub fn mobius_transform(&mut self)
+    where
+        V: for<'v> SubAssign<&'v V>,
+    {
+        self.for_each_subset_pair(|sub, sup| *sup -= sub)
+    }
+    /// For each element i and each set S without i, calls f(value of S, value of S ∪ {i}),
+    /// where all pairs for element i are visited before those of i+1.
+    fn for_each_subset_pair<F: FnMut(&V, &mut V)>(&mut self, mut f: F) {
+        for i in 0..self.universe_size {
+            let bit = 1 << i;
+            // each block of 2 * bit values consists of sets without i, followed by the same sets with i
+            for block in self.values.chunks_mut(bit << 1) {
+                let (without, with) = block.split_at_mut(bit);
+                for (sub, sup) in without.iter().zip(with.iter_mut()) {
+                    f(sub, sup);
+                }
+            }
+        }
+    }
+}
+
+impl<V: Default> SubsetMap<V> {
+    /// Panics if universe_size >= usize::BITS
+    pub fn new(universe_size: usize) -> Self {
+        Self::from_fn(universe_size, |_| V::default())
+    }
+}
+
+impl<V, K: ChunkRead + ?Sized> core::ops::Index<&K> for SubsetMap<V> {
+    type Output = V;
+    /// Panics if the key has elements outside the universe.
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key has elements outside the universe of SubsetMap")
+    }
+}
+impl<V, K: ChunkRead + ?Sized> core::ops::IndexMut<&K> for SubsetMap<V> {
+    /// Panics if the key has elements outside the universe.
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key).expect("key has elements outside the universe of SubsetMap")
+    }
+}
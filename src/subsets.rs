@@ -0,0 +1,145 @@
+//! Streaming enumeration of families of sets.
+//! Each enumerator owns one buffered IndexSet, which it updates in place and lends out by reference,
+//! s.t. enumerating does not allocate per item.
+use super::{ChunkRead, Index, IndexSet};
+
+/// Enumerates all subsets of some set, from the set itself down to the empty set,
+/// in descending powerset order (see IndexSet::try_decrease_in_powerset_order).
+pub struct Submasks<const N: usize> {
+    inner: MaskedSubsets<N>,
+}
+
+/// Enumerates all supersets of some set within the universe of indices 0..universe_bound,
+/// from the largest down to the set itself, in descending powerset order.
+/// Elements of the set outside the universe are in every superset.
+pub struct Supersets<const N: usize> {
+    inner: MaskedSubsets<N>,
+}
+
+/// Enumerates all k-element subsets of the universe of indices 0..universe_bound,
+/// in colexicographic order, i.e., ascending powerset order: {0,1,2}, {0,1,3}, {0,2,3}, {1,2,3}, {0,1,4}, ...
+pub struct KSubsets<const N: usize> {
+    buffered: IndexSet<N>,
+    universe_bound: Index,
+    // already_returned_buffered is initially FALSE. then becomes and stays true
+    already_returned_buffered: bool,
+    exhausted: bool,
+}
+
+/// Enumerates fixed ∪ T for every subset T of free, in descending powerset order of T.
+struct MaskedSubsets<const N: usize> {
+    fixed: IndexSet<N>,
+    free: IndexSet<N>,
+    // invariant: buffered.chunk_count() covers all nonzero chunks of fixed and free
+    buffered: IndexSet<N>,
+    // already_returned_buffered is initially FALSE. then becomes and stays true
+    already_returned_buffered: bool,
+    exhausted: bool,
+}
+
+///////////////////////////////////////////////////////////////////////
+
+impl<const N: usize> MaskedSubsets<N> {
+    fn new(fixed: IndexSet<N>, free: IndexSet<N>) -> Self {
+        let buffered = &fixed | &free;
+        Self { fixed, free, buffered, already_returned_buffered: false, exhausted: false }
+    }
+    /// Replaces T with the previous subset of free in powerset order. Returns false if T was empty.
+    fn try_decrease(&mut self) -> bool {
+        let chunks = self.buffered.as_chunks_mut();
+        let fixed = self.fixed.as_chunks();
+        let fixed_chunk = |idx_of_chunk| fixed.get(idx_of_chunk).copied().unwrap_or(0);
+        // like decrementing T and masking with free: the lowest nonzero chunk of T is decremented,
+        // and lower chunks of T underflow to their maximum, i.e., their chunk in free.
+        for (idx_of_chunk, free_chunk) in self.free.iter_nonzero_chunks() {
+            let sub = chunks[idx_of_chunk] & free_chunk;
+            if sub != 0 {
+                chunks[idx_of_chunk] = fixed_chunk(idx_of_chunk) | ((sub - 1) & free_chunk);
+                return true;
+            }
+            chunks[idx_of_chunk] = fixed_chunk(idx_of_chunk) | free_chunk;
+        }
+        // T was empty, and has wrapped around to free
+        false
+    }
+    fn next_subset(&mut self) -> Option<&IndexSet<N>> {
+        if self.exhausted {
+            return None;
+        }
+        if self.already_returned_buffered {
+            if !self.try_decrease() {
+                self.exhausted = true;
+                return None;
+            }
+        } else {
+            self.already_returned_buffered = true;
+        }
+        Some(&self.buffered)
+    }
+}
+
+impl<const N: usize> Submasks<N> {
+    pub fn new<A: ChunkRead + ?Sized>(set: &A) -> Self {
+        Self { inner: MaskedSubsets::new(IndexSet::default(), set.to_index_set()) }
+    }
+    pub fn next_subset(&mut self) -> Option<&IndexSet<N>> {
+        self.inner.next_subset()
+    }
+}
+
+impl<const N: usize> Supersets<N> {
+    pub fn new<A: ChunkRead + ?Sized>(set: &A, universe_bound: Index) -> Self {
+        let fixed = set.to_index_set();
+        let mut free = IndexSet::with_min_capacity(universe_bound);
+        free.insert_all_in_range(0..universe_bound);
+        free -= &fixed;
+        Self { inner: MaskedSubsets::new(fixed, free) }
+    }
+    pub fn next_superset(&mut self) -> Option<&IndexSet<N>> {
+        self.inner.next_subset()
+    }
+}
+
+impl<const N: usize> KSubsets<N> {
+    pub fn new(universe_bound: Index, k: usize) -> Self {
+        let mut buffered = IndexSet::with_min_capacity(universe_bound);
+        buffered.insert_all_in_range(0..k.min(universe_bound));
+        Self {
+            buffered,
+            universe_bound,
+            already_returned_buffered: false,
+            exhausted: universe_bound < k,
+        }
+    }
+    /// Replaces the buffered subset with the next in colex order, if there is one.
+    fn try_increase(&mut self) -> bool {
+        // the lowest run of elements p..q becomes {q} ∪ 0..(q-p-1)
+        let p = match self.buffered.min_element() {
+            Some(p) => p,
+            // k == 0: the empty set was the only subset
+            None => return false,
+        };
+        let q = self.buffered.next_absent_from(p).unwrap_or(Index::MAX);
+        if self.universe_bound <= q {
+            return false;
+        }
+        self.buffered.remove_all_in_range(p..q);
+        self.buffered.insert(q);
+        self.buffered.insert_all_in_range(0..q - p - 1);
+        true
+    }
+    pub fn next_subset(&mut self) -> Option<&IndexSet<N>> {
+        if self.exhausted {
+            return None;
+        }
+        if self.already_returned_buffered {
+            if !self.try_increase() {
+                self.exhausted = true;
+                return None;
+            }
+        } else {
+            self.already_returned_buffered = true;
+        }
+        Some(&self.buffered)
+    }
+}
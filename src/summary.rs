@@ -0,0 +1,82 @@
+use super::{index_count_to_chunk_count, Chunk, ChunkBitAddr, Word};
+
+/// Hierarchical record of which chunks (or words, see IndexSet) of some slice are nonzero.
+/// Level 0 has one bit per chunk, set iff that chunk is nonzero.
+/// Level L+1 has one bit per chunk of level L, set iff that chunk is nonzero.
+/// The last level has at most one chunk, so finding the next nonzero chunk takes O(levels) steps.
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkSummary {
+    levels: Vec<Vec<Chunk>>,
+    // if true, levels may disagree with the chunks they summarize and must not be trusted.
+    pub(crate) stale: bool,
+}
+
+impl ChunkSummary {
+    pub(crate) fn new<W: Word>(chunks: &[W]) -> Self {
+        let mut me = Self { levels: vec![], stale: false };
+        me.rebuild(chunks);
+        me
+    }
+    pub(crate) fn rebuild<W: Word>(&mut self, chunks: &[W]) {
+        self.levels.clear();
+        let mut level = Self::summarize_level(chunks);
+        while level.len() > 1 {
+            let next_level = Self::summarize_level(&level);
+            self.levels.push(level);
+            level = next_level;
+        }
+        self.levels.push(level);
+        self.stale = false;
+    }
+    fn summarize_level<W: Word>(chunks: &[W]) -> Vec<Chunk> {
+        let mut level = vec![0; index_count_to_chunk_count(chunks.len())];
+        for (idx_of_chunk, &chunk) in chunks.iter().enumerate() {
+            if chunk != W::ZERO {
+                let cba = ChunkBitAddr::from_bit_idx(idx_of_chunk);
+                level[cba.idx_of_chunk] |= cba.chunk_mask();
+            }
+        }
+        level
+    }
+    /// Records whether the summarized chunk at the given index is nonzero.
+    pub(crate) fn update(&mut self, idx_of_chunk: usize, mut nonzero: bool) {
+        let mut bit_idx = idx_of_chunk;
+        for level in self.levels.iter_mut() {
+            let cba = ChunkBitAddr::from_bit_idx(bit_idx);
+            let word = &mut level[cba.idx_of_chunk];
+            let was_nonzero = *word != 0;
+            if nonzero {
+                *word |= cba.chunk_mask();
+            } else {
+                *word &= !cba.chunk_mask();
+            }
+            nonzero = *word != 0;
+            if nonzero == was_nonzero {
+                // levels above are unaffected
+                return;
+            }
+            bit_idx = cba.idx_of_chunk;
+        }
+    }
+    /// Returns the index of the first nonzero summarized chunk at or after idx_of_chunk.
+    pub(crate) fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<usize> {
+        self.next_set_bit(0, idx_of_chunk)
+    }
+    fn next_set_bit(&self, level: usize, bit_idx: usize) -> Option<usize> {
+        let words = &self.levels[level];
+        let cba = ChunkBitAddr::from_bit_idx(bit_idx);
+        let masked = words.get(cba.idx_of_chunk)? & (!0 << cba.idx_in_chunk);
+        let idx_of_chunk = if masked != 0 {
+            cba.idx_of_chunk
+        } else if level + 1 < self.levels.len() {
+            // the level above knows which of our words is the next nonzero one
+            self.next_set_bit(level + 1, cba.idx_of_chunk + 1)?
+        } else {
+            // top level has at most one word
+            let rest = &words[cba.idx_of_chunk + 1..];
+            cba.idx_of_chunk + 1 + rest.iter().position(|&word| word != 0)?
+        };
+        let word = if masked != 0 { masked } else { words[idx_of_chunk] };
+        Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk: word.trailing_zeros() }.to_bit_idx())
+    }
+}
@@ -0,0 +1,36 @@
+use super::combinators::un_ops::Truncate;
+use super::{ChunkRead, Index, IndexSet};
+
+/// Returns the sumset (or Minkowski sum) {i + j | i in a, j in b}, restricted to sums less than bound.
+/// ORs a copy of one set, shifted by each element of the other, so it takes
+/// O(min(a.len(), b.len()) * bound / usize::BITS) chunk operations.
+pub fn sumset<const N: usize, A, B>(a: &A, b: &B, bound: Index) -> IndexSet<N>
+where
+    A: ChunkRead + ?Sized,
+    B: ChunkRead + ?Sized,
+{
+    if b.len() <= a.len() {
+        shifted_ors(a, b, bound)
+    } else {
+        shifted_ors(b, a, bound)
+    }
+}
+
+/// ORs together copies of a shifted by each element of shifts, restricted to indices less than bound.
+fn shifted_ors<const N: usize, A, B>(a: &A, shifts: &B, bound: Index) -> IndexSet<N>
+where
+    A: ChunkRead + ?Sized,
+    B: ChunkRead + ?Sized,
+{
+    // no sum exceeds max(a) + max(shifts), however large bound is
+    let max_sum = match (a.max_element(), shifts.max_element()) {
+        (Some(max_a), Some(max_shift)) => max_a.saturating_add(max_shift),
+        _ => return IndexSet::default(),
+    };
+    let mut sums = IndexSet::with_min_capacity(bound.min(max_sum.saturating_add(1)));
+    let a = a.map_chunks(Truncate { bound });
+    for shift in shifts.iter().take_while(|&shift| shift < bound) {
+        sums |= &a.shifted_by(shift).map_chunks(Truncate { bound });
+    }
+    sums
+}
@@ -1,9 +1,11 @@
 use crate::combinators::bin_ops::Or;
+use crate::combinators::EmptyIndexSet;
 use crate::*;
-use core::ops::Range;
+use core::ops::{Range, RangeInclusive};
 
 use std::collections::HashSet;
 use std::iter::FromIterator;
+use subsets::{KSubsets, Submasks, Supersets};
 
 type HSet = HashSet<usize>;
 const RANGES: &[Range<Index>] = &[
@@ -152,7 +154,7 @@ fn stack_chunks() {
 
 #[test]
 fn combine_overwrite_is_ok() {
-    for range in RANGES.iter().cloned() {
+    for range in RANGES {
         let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
         let b = IndexSet::<2>::from_iter(stream(1, range.clone()));
 
@@ -194,6 +196,44 @@ fn powerset_order() {
     assert_eq!(count, 0b11010);
 }
 
+#[test]
+fn powerset_increment_and_rank() {
+    let mut set = IndexSet::<1>::default();
+    let mut prev = set.clone();
+    for expected_rank in 1..300 {
+        assert!(set.try_increase_in_powerset_order());
+        assert_eq!(set.powerset_rank(), vec![expected_rank]);
+        assert_eq!(set.powerset_cmp(&prev), core::cmp::Ordering::Greater);
+        assert_eq!(prev.powerset_cmp(&set), core::cmp::Ordering::Less);
+        prev = set.clone();
+    }
+    while set.try_decrease_in_powerset_order() {}
+    assert!(set.is_empty());
+
+    // carry across chunk boundaries, growing the set
+    let mut set = IndexSet::<1>::from_chunk_slice(&[!0]);
+    set.enable_summary();
+    assert!(set.try_increase_in_powerset_order());
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![usize::BITS as usize]);
+    assert_eq!(set.powerset_rank(), vec![0, 1]);
+    assert!(set.try_decrease_in_powerset_order());
+    assert_eq!(set.powerset_rank(), vec![!0]);
+    assert_eq!(set.len(), usize::BITS as usize);
+
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range));
+        assert_eq!(IndexSet::<1>::from_powerset_rank(&a.powerset_rank()), a.to_index_set());
+        let (ra, rb) = (a.powerset_rank(), b.powerset_rank());
+        let ord = ra.len().cmp(&rb.len()).then_with(|| ra.iter().rev().cmp(rb.iter().rev()));
+        assert_eq!(a.powerset_cmp(&b), ord);
+        assert_eq!(a.powerset_cmp(&b), powerset_cmp_naive(&a, &b));
+        assert_eq!(b.powerset_cmp(&a), ord.reverse());
+        let padded = IndexSet::<1>::from_chunk_iter(ra.iter().copied().chain([0, 0]));
+        assert_eq!(a.powerset_cmp(&padded), core::cmp::Ordering::Equal);
+    }
+}
+
 #[test]
 fn insert_all_in_range() {
     let rng = fastrand::Rng::with_seed(31644);
@@ -256,3 +296,1362 @@ fn chunk_list_cmp() {
         b.clear();
     }
 }
+
+#[test]
+fn summary_matches_chunks() {
+    let rng = fastrand::Rng::with_seed(4521);
+    const TESTS: usize = 2_000;
+    let mut a = IndexSet::<1>::default();
+    let mut b = IndexSet::<1>::default();
+    b.enable_summary();
+    for _ in 0..TESTS {
+        let index = rng.usize(0..1 << 14);
+        match rng.u8(0..4) {
+            0 => assert_eq!(a.insert(index), b.insert(index)),
+            1 => assert_eq!(a.remove(index), b.remove(index)),
+            2 => {
+                let range = index..index + rng.usize(0..300);
+                a.insert_all_in_range(range.clone());
+                b.insert_all_in_range(range);
+            }
+            _ => {
+                let range = index..index + rng.usize(0..3000);
+                a.remove_all_in_range(range.clone());
+                b.remove_all_in_range(range);
+            }
+        }
+        assert!(a.iter().eq(b.iter()));
+        assert_eq!(a.min_element(), b.min_element());
+        assert_eq!(a.len(), b.len());
+    }
+    // writes through as_chunks_mut are picked up by the summary
+    const B: usize = usize::BITS as usize;
+    b.clear();
+    b.as_chunks_mut()[3] = 0b101;
+    assert_eq!(b.iter().collect::<Vec<_>>(), vec![3 * B, 3 * B + 2]);
+    b.insert(1);
+    assert_eq!(b.iter().collect::<Vec<_>>(), vec![1, 3 * B, 3 * B + 2]);
+    a.clear();
+    a.insert_all(&b);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn summary_skips_zero_chunks() {
+    let mut a = IndexSet::<1>::default();
+    a.enable_summary();
+    a.insert(5);
+    a.insert(50_000_000);
+    let mut b = IndexSet::<1>::default();
+    b.enable_summary();
+    b.insert_all_in_range(49_999_990..50_000_010);
+    assert_eq!(a.iter().collect::<Vec<_>>(), vec![5, 50_000_000]);
+    assert_eq!(a.and(&b).iter().collect::<Vec<_>>(), vec![50_000_000]);
+    assert_eq!(a.or(&b).len(), 21);
+    assert_eq!(b.without(&a).min_element(), Some(49_999_990));
+    assert!(!a.is_disjoint_with(&b));
+    a.remove(50_000_000);
+    assert!(a.is_disjoint_with(&b));
+}
+
+#[test]
+fn successor_and_predecessor() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range.clone()));
+        let a_and_b = a.and(&b);
+        for i in 0..range.end + 70 {
+            let element_from = (i..range.end).find(|&j| a_and_b.contains(j));
+            let element_before = (0..i).rev().find(|&j| a_and_b.contains(j));
+            let absent_from = (i..).find(|&j| !a_and_b.contains(j));
+            let absent_before = (0..i).rev().find(|&j| !a_and_b.contains(j));
+            assert_eq!(a_and_b.next_index_from(i), element_from);
+            assert_eq!(a_and_b.prev_index_before(i), element_before);
+            assert_eq!(a_and_b.next_absent_from(i), absent_from);
+            assert_eq!(a_and_b.prev_absent_before(i), absent_before);
+        }
+    }
+    let full = IndexSet::<1>::from_chunk_slice(&[!0, !0]);
+    assert_eq!(full.next_absent_from(3), Some(2 * usize::BITS as usize));
+    assert_eq!(full.prev_absent_before(2 * usize::BITS as usize), None);
+    assert_eq!(full.prev_index_before(0), None);
+}
+
+#[test]
+fn double_ended_iteration() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range));
+        let a_or_b = a.or(&b);
+        let forward: Vec<Index> = a_or_b.iter().collect();
+        let mut backward: Vec<Index> = a_or_b.iter().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        // alternate between both ends; every element is yielded exactly once
+        let mut it = a_or_b.iter();
+        let mut front = vec![];
+        let mut back = vec![];
+        loop {
+            match (it.next(), it.next_back()) {
+                (Some(x), Some(y)) => {
+                    front.push(x);
+                    back.push(y);
+                }
+                (Some(x), None) | (None, Some(x)) => front.push(x),
+                (None, None) => break,
+            }
+        }
+        front.extend(back.into_iter().rev());
+        assert_eq!(forward, front);
+    }
+}
+
+#[test]
+fn range_iteration() {
+    let rng = fastrand::Rng::with_seed(7723);
+    let a = IndexSet::<2>::from_iter(stream(3, 0..900));
+    for _ in 0..500 {
+        let start = rng.usize(0..1000);
+        let end = rng.usize(start..1100);
+        let expected: Vec<Index> = a.iter().filter(|i| (start..end).contains(i)).collect();
+        assert_eq!(a.iter_range(start..end).collect::<Vec<_>>(), expected);
+        let mut backward: Vec<Index> = a.iter_range(start..end).rev().collect();
+        backward.reverse();
+        assert_eq!(backward, expected);
+        let inclusive: Vec<Index> = a.iter_range(start..=end).collect();
+        assert_eq!(inclusive.len(), expected.len() + a.contains(end) as usize);
+    }
+    assert_eq!(a.iter_range(..).count(), a.len());
+    assert_eq!(a.iter_range(5..).next_back(), a.max_element());
+    assert_eq!(a.iter_range(10..10).next(), None);
+    let (start, end) = (10, 3);
+    assert_eq!(a.iter_range(start..end).next_back(), None);
+
+    // records the greatest chunk index it was asked for
+    struct Probe<'a>(&'a IndexSet<1>, core::cell::Cell<usize>);
+    impl ChunkRead for Probe<'_> {
+        fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+            self.1.set(self.1.get().max(idx_of_chunk));
+            self.0.get_chunk(idx_of_chunk)
+        }
+        fn zero_chunks_from_conservative(&self) -> usize {
+            self.0.zero_chunks_from_conservative()
+        }
+        fn next_nonzero_chunk_from(&self, idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+            self.1.set(self.1.get().max(idx_of_chunk));
+            self.0.next_nonzero_chunk_from(idx_of_chunk)
+        }
+    }
+    const B: usize = usize::BITS as usize;
+    let b = IndexSet::<1>::from_iter([5, B + 6, 1000 * B]);
+    let probe = Probe(&b, Default::default());
+    assert_eq!(probe.iter_range(0..10).collect::<Vec<_>>(), [5]);
+    assert_eq!(probe.1.get(), 0);
+    assert_eq!(probe.iter_range(B..2 * B).collect::<Vec<_>>(), [B + 6]);
+    assert_eq!(probe.1.get(), 1);
+}
+
+#[test]
+fn rank_and_select() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range.clone()));
+        let a_xor_b = a.xor(&b);
+        let ranked = a_xor_b.ranked();
+        let elements: Vec<Index> = a_xor_b.iter().collect();
+        for i in 0..range.end + 70 {
+            let rank = elements.iter().filter(|&&j| j < i).count();
+            assert_eq!(a_xor_b.rank(i), rank);
+            assert_eq!(ranked.rank(i), rank);
+        }
+        for k in 0..elements.len() + 3 {
+            assert_eq!(a_xor_b.select(k), elements.get(k).copied());
+            assert_eq!(ranked.select(k), elements.get(k).copied());
+        }
+    }
+}
+
+#[test]
+fn rank_directory_rebuild() {
+    let mut set = IndexSet::<1>::from_iter([3, 900, 901, 5000]);
+    let mut directory = RankDirectory::new(&set);
+    assert_eq!(directory.select(&set, 2), Some(901));
+    set.insert(4);
+    directory.rebuild(&set);
+    assert_eq!(directory.len(), 5);
+    assert_eq!(directory.rank(&set, 901), 3);
+    assert_eq!(directory.select(&set, 4), Some(5000));
+}
+
+#[test]
+fn roaring_matches_index_set() {
+    let rng = fastrand::Rng::with_seed(9191);
+    const TESTS: usize = 1_000;
+    const N: usize = 5 << 16;
+    let mut a = IndexSet::<1>::default();
+    let mut b = RoaringIndexSet::new();
+    for test in 0..TESTS {
+        let index = rng.usize(0..N);
+        match rng.u8(0..6) {
+            0 | 1 => assert_eq!(a.insert(index), b.insert(index)),
+            2 => assert_eq!(a.remove(index), b.remove(index)),
+            3 => {
+                let range = index..index + rng.usize(0..9000);
+                a.insert_all_in_range(range.clone());
+                b.insert_all_in_range(range);
+            }
+            4 => {
+                let range = index..index + rng.usize(0..9000);
+                a.remove_all_in_range(range.clone());
+                b.remove_all_in_range(range);
+            }
+            _ => {
+                let c = IndexSet::<1>::from_iter(stream(test as u64, index..index + 300));
+                a.insert_all(&c);
+                b.insert_all(&c);
+            }
+        }
+        if test % 100 == 0 {
+            b.optimize();
+        }
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.contains(index), b.contains(index));
+    }
+    assert!(a.iter().eq(b.iter()));
+    assert_eq!(a.xor(&b).len(), 0);
+    assert_eq!(b, RoaringIndexSet::from_chunk_read(&a));
+    assert_eq!(b.to_index_set::<1>(), a);
+}
+
+#[test]
+fn roaring_insert_all_merges_chunks() {
+    let rng = fastrand::Rng::with_seed(5150);
+    const B: usize = usize::BITS as usize;
+    let alternating: Vec<Chunk> = vec![0x5555_5555_5555_5555_u64 as Chunk; 40];
+    let dense: Vec<Chunk> = vec![!0; 1100];
+    let sparse = IndexSet::<1>::from_iter([3, 64, 65, 127, 128, 5000, 70_000]);
+    let mut random = IndexSet::<1>::default();
+    for _ in 0..3000 {
+        random.insert(rng.usize(0..1 << 17));
+    }
+    let sources: [&dyn Fn(usize) -> IndexSet<1>; 4] = [
+        &|offset| IndexSet::<1>::from_chunk_slice(&alternating).shifted_by(offset).to_index_set(),
+        &|offset| IndexSet::<1>::from_chunk_slice(&dense).shifted_by(offset).to_index_set(),
+        &|offset| sparse.shifted_by(offset).to_index_set(),
+        &|offset| random.shifted_by(offset).to_index_set(),
+    ];
+    // sets stored as arrays, runs and bitmaps, respectively
+    let mut runs = RoaringIndexSet::new();
+    runs.insert_all_in_range(10..3 * B + 1);
+    runs.insert_all_in_range(60_000..70_000);
+    let starts = [
+        RoaringIndexSet::from_iter([1, 100, 129, 70_001]),
+        runs,
+        RoaringIndexSet::from_chunk_read(&random),
+    ];
+    for start in starts.iter() {
+        for source in sources.iter() {
+            for offset in [0, 1, B - 1, 3 * B + 5, 60_000] {
+                let other = source(offset);
+                let mut a = start.clone();
+                a.insert_all(&other);
+                let expected = start.to_index_set::<1>().or(&other).to_index_set::<1>();
+                assert_eq!(a.len(), expected.len());
+                assert!(a.xor(&expected).is_empty());
+                assert_eq!(
+                    a.iter_runs().collect::<Vec<_>>(),
+                    expected.iter_runs().collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn roaring_huge_indices() {
+    let big = usize::MAX - 5;
+    let mut a = RoaringIndexSet::from_iter([3, 1 << 40, big]);
+    assert_eq!(a.iter().collect::<Vec<_>>(), vec![3, 1 << 40, big]);
+    assert_eq!(a.max_element(), Some(big));
+    a.insert_all_in_range(big - 70_000..big);
+    assert_eq!(a.len(), 70_003);
+    let b = RoaringIndexSet::from_iter([7, big - 1]);
+    assert_eq!(a.and(&b).iter().collect::<Vec<_>>(), vec![big - 1]);
+    assert_eq!(b.without(&a).iter().next(), Some(7));
+
+    // comparisons skip the zero chunks below big
+    use core::cmp::Ordering::*;
+    let c = RoaringIndexSet::from_iter([big]);
+    let d = RoaringIndexSet::from_iter([3, big]);
+    assert!(c.is_subset_of(&d) && d.is_superset_of(&c) && !d.is_subset_of(&c));
+    assert!(c.is_subset_of(&a) && !b.is_subset_of(&a));
+    assert_eq!(c.set_cmp(&c), Some(Equal));
+    assert_eq!(b.set_cmp(&c), None);
+    assert_eq!((c.chunk_list_cmp(&d), d.chunk_list_cmp(&c)), (Less, Greater));
+    assert_eq!((c.powerset_cmp(&d), c.powerset_cmp(&b)), (Less, Greater));
+    assert_eq!(c.powerset_cmp(&c), Equal);
+    assert!(c.is_superset_of(&EmptyIndexSet) && !c.is_subset_of(&IndexSet::<1>::from_iter([3])));
+}
+
+#[test]
+fn paged_matches_index_set() {
+    let rng = fastrand::Rng::with_seed(6061);
+    const TESTS: usize = 1_000;
+    const N: usize = 1 << 16;
+    let mut a = IndexSet::<1>::default();
+    let mut b = PagedIndexSet::new();
+    for _ in 0..TESTS {
+        let index = rng.usize(0..N);
+        match rng.u8(0..4) {
+            0 => assert_eq!(a.insert(index), b.insert(index)),
+            1 => assert_eq!(a.remove(index), b.remove(index)),
+            2 => {
+                let range = index..index + rng.usize(0..5000);
+                a.insert_all_in_range(range.clone());
+                b.insert_all_in_range(range);
+            }
+            _ => {
+                let range = index..index + rng.usize(0..9000);
+                a.remove_all_in_range(range.clone());
+                b.remove_all_in_range(range);
+            }
+        }
+        assert_eq!(a.len(), b.len());
+    }
+    assert!(a.iter().eq(b.iter()));
+    assert!(a.xor(&b).is_empty());
+    let mut c = PagedIndexSet::new();
+    c.insert_all(&a);
+    assert_eq!(b, c);
+    c.remove_all(&b);
+    assert_eq!(c.page_count(), 0);
+
+    // ranges starting and ending within, or at the edges of, pages and chunks
+    const PAGE: usize = 64 * usize::BITS as usize;
+    let ranges =
+        [0..1, 5..70, 64..128, PAGE - 1..PAGE + 1, 3..5 * PAGE + 17, PAGE..3 * PAGE, 100..PAGE];
+    for range in ranges {
+        let mut a = IndexSet::<1>::from_iter([2, PAGE + 3]);
+        let mut b = PagedIndexSet::from_iter([2, PAGE + 3]);
+        a.insert_all_in_range(range.clone());
+        b.insert_all_in_range(range.clone());
+        assert!(a.xor(&b).is_empty());
+        let pages: HashSet<usize> = a.iter().map(|index| index / PAGE).collect();
+        assert_eq!(b.page_count(), pages.len());
+    }
+}
+
+#[test]
+fn paged_huge_indices() {
+    let mut a = PagedIndexSet::new();
+    a.insert(usize::MAX - 1);
+    a.insert(9);
+    assert_eq!(a.page_count(), 2);
+    assert_eq!(a.iter().collect::<Vec<_>>(), vec![9, usize::MAX - 1]);
+    // missing interior pages read as zero chunks
+    assert_eq!(a.get_chunk(1 << 20), Some(0));
+    assert_eq!(a.max_element(), Some(usize::MAX - 1));
+    a.insert_all_in_range(usize::MAX - 300..usize::MAX);
+    assert_eq!(a.len(), 301);
+    a.remove_all_in_range(0..usize::MAX - 2);
+    assert_eq!(a.iter().collect::<Vec<_>>(), vec![usize::MAX - 2, usize::MAX - 1]);
+    let b = IndexSet::<1>::from_iter([3, 9]);
+    assert!(a.is_disjoint_with(&b));
+
+    // comparisons skip the missing pages below the huge indices
+    let c = PagedIndexSet::from_iter([usize::MAX - 5]);
+    let d = PagedIndexSet::from_iter([usize::MAX - 5]);
+    assert!(c.is_subset_of(&d) && c.is_superset_of(&d));
+    assert_eq!(c.set_cmp(&d), Some(core::cmp::Ordering::Equal));
+    assert_eq!(c.set_cmp(&a), None);
+    assert!(!a.is_subset_of(&b) && !b.is_subset_of(&a));
+    assert_eq!(a.chunk_list_cmp(&c), core::cmp::Ordering::Greater);
+    assert_eq!(a.powerset_cmp(&c), core::cmp::Ordering::Greater);
+}
+
+#[test]
+fn interval_set_matches_index_set() {
+    let rng = fastrand::Rng::with_seed(1187);
+    const TESTS: usize = 2_000;
+    let mut a = IndexSet::<1>::default();
+    let mut b = IntervalSet::new();
+    for _ in 0..TESTS {
+        let index = rng.usize(0..2000);
+        match rng.u8(0..4) {
+            0 => assert_eq!(a.insert(index), b.insert(index)),
+            1 => assert_eq!(a.remove(index), b.remove(index)),
+            2 => {
+                let range = index..index + rng.usize(0..200);
+                a.insert_all_in_range(range.clone());
+                b.insert_all_in_range(range);
+            }
+            _ => {
+                let range = index..index + rng.usize(0..100);
+                a.remove_all_in_range(range.clone());
+                b.remove_all_in_range(range);
+            }
+        }
+        assert_eq!(a.len(), b.len());
+    }
+    assert!(a.iter().eq(b.iter()));
+    assert_eq!(b.to_index_set::<1>(), a);
+    assert_eq!(IntervalSet::from_chunk_read(&a), b);
+    assert!(b.runs().windows(2).all(|w| w[0].end < w[1].start));
+}
+
+#[test]
+fn interval_set_algebra() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range));
+        let (ia, ib) = (IntervalSet::from_chunk_read(&a), IntervalSet::from_chunk_read(&b));
+        assert_eq!(ia.union(&ib), IntervalSet::from_chunk_read(&a.or(&b)));
+        assert_eq!(ia.intersection(&ib), IntervalSet::from_chunk_read(&a.and(&b)));
+        assert_eq!(ia.difference(&ib), IntervalSet::from_chunk_read(&a.without(&b)));
+        assert_eq!(ib.difference(&ia), IntervalSet::from_chunk_read(&b.without(&a)));
+    }
+    let a: IntervalSet = [0..10, 20..30, 1 << 60..(1 << 60) + 5].into_iter().collect();
+    let mut b = IntervalSet::new();
+    b.insert_all_in_range(5..25);
+    assert_eq!(a.union(&b).runs(), &[0..30, 1 << 60..(1 << 60) + 5]);
+    assert_eq!(a.intersection(&b).runs(), &[5..10, 20..25]);
+    assert_eq!(a.difference(&b).runs(), &[0..5, 25..30, 1 << 60..(1 << 60) + 5]);
+    assert_eq!(a.and(&b).iter().count(), 10);
+}
+
+#[test]
+fn runs_near_max_index() {
+    const MAX: Index = Index::MAX;
+    const B: usize = usize::BITS as usize;
+    let runs = |a: &RoaringIndexSet| a.iter_runs().collect::<Vec<_>>();
+    assert_eq!(runs(&RoaringIndexSet::from_iter([MAX])), [MAX..=MAX]);
+    assert_eq!(runs(&RoaringIndexSet::from_iter([3, MAX - 1, MAX])), [3..=3, MAX - 1..=MAX]);
+    let a = RoaringIndexSet::from_iter((MAX - 2 * B..=MAX).chain([5]));
+    assert_eq!(runs(&a), [5..=5, MAX - 2 * B..=MAX]);
+    let a = RoaringIndexSet::from_iter((MAX - B - 1..MAX).chain([5]));
+    assert_eq!(runs(&a), [5..=5, MAX - B - 1..=MAX - 1]);
+    let a = RoaringIndexSet::from_iter([0, MAX - B, MAX]);
+    assert_eq!(runs(&a), [0..=0, MAX - B..=MAX - B, MAX..=MAX]);
+    assert_eq!(a.iter_gaps(MAX).collect::<Vec<_>>(), [1..MAX - B, MAX - B + 1..MAX]);
+    let b = IntervalSet::from_chunk_read(&a);
+    assert_eq!(b.iter().collect::<Vec<_>>(), [0, MAX - B, MAX]);
+
+    // the runs cover exactly the set, on every set type
+    let sets = [
+        vec![MAX],
+        vec![0, MAX],
+        vec![MAX - 1, MAX],
+        vec![5, MAX - B, MAX],
+        [5].into_iter().chain(MAX - 2 * B..=MAX).collect(),
+        (MAX - B..MAX).collect(),
+    ];
+    for indices in sets {
+        let roaring = RoaringIndexSet::from_iter(indices.iter().copied());
+        let paged = PagedIndexSet::from_iter(indices.iter().copied());
+        let interval = IntervalSet::from_iter(indices.iter().copied());
+        let from_runs = |runs: Vec<RangeInclusive<Index>>| {
+            let covered: Vec<Index> = runs.iter().flat_map(|run| run.clone()).collect();
+            assert!(runs.windows(2).all(|w| w[0].end() + 1 < *w[1].start()));
+            covered
+        };
+        assert_eq!(from_runs(roaring.iter_runs().collect()), indices);
+        assert_eq!(from_runs(paged.iter_runs().collect()), indices);
+        assert_eq!(from_runs(ChunkRead::iter_runs(&interval).collect()), indices);
+        assert_eq!(from_runs(interval.iter_runs().collect()), indices);
+        let mut rev_runs: Vec<_> = interval.iter_runs().rev().collect();
+        rev_runs.reverse();
+        assert_eq!(rev_runs, interval.iter_runs().collect::<Vec<_>>());
+        assert_eq!(IntervalSet::from_chunk_read(&paged), interval);
+    }
+}
+
+#[test]
+fn interval_set_near_max_index() {
+    const MAX: Index = Index::MAX;
+    let mut a = IntervalSet::new();
+    assert!(a.insert(MAX));
+    assert!(!a.insert(MAX));
+    assert!(a.contains(MAX) && !a.contains(MAX - 1));
+    assert_eq!((a.len(), a.min_element(), a.max_element()), (1, Some(MAX), Some(MAX)));
+    assert_eq!(a.iter().collect::<Vec<_>>(), [MAX]);
+    assert!(a.runs().is_empty() && !a.is_empty());
+    assert_eq!(format!("{:?}", a), format!("{{{}..={}}}", MAX, MAX));
+
+    let mut b = IntervalSet::new();
+    b.insert_all_in_range(MAX - 3..MAX);
+    assert_eq!(b.iter().collect::<Vec<_>>(), [MAX - 3, MAX - 2, MAX - 1]);
+    assert_eq!(b.max_element(), Some(MAX - 1));
+    let c = a.union(&b);
+    assert_eq!(c.iter().collect::<Vec<_>>(), [MAX - 3, MAX - 2, MAX - 1, MAX]);
+    assert_eq!(c.get_chunk(MAX / usize::BITS as usize), Some(0b1111 << (usize::BITS - 4)));
+    assert_eq!(c.iter().next_back(), Some(MAX));
+    assert_eq!(
+        c.set_cmp(&RoaringIndexSet::from_iter(MAX - 3..=MAX)),
+        Some(core::cmp::Ordering::Equal)
+    );
+    assert_eq!(c.intersection(&a), a);
+    assert_eq!(c.difference(&a), b);
+    assert!(a.difference(&c).is_empty());
+
+    let mut d = c.clone();
+    assert!(d.remove(MAX));
+    assert!(!d.remove(MAX));
+    assert_eq!(d, b);
+    assert!(d.remove(MAX - 1));
+    assert_eq!(d.iter().collect::<Vec<_>>(), [MAX - 3, MAX - 2]);
+    let mut e = c;
+    e.clear();
+    assert!(e.is_empty() && !e.contains(MAX));
+}
+
+#[test]
+fn runs_and_gaps() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range.clone()));
+        let a_or_b = a.or(&b);
+        let mut from_runs = IndexSet::<2>::default();
+        for run in a_or_b.iter_runs() {
+            assert!(!a_or_b.contains(run.start().wrapping_sub(1)) || *run.start() == 0);
+            assert!(!a_or_b.contains(run.end() + 1));
+            from_runs.insert_all_in_range(*run.start()..run.end() + 1);
+        }
+        assert!(a_or_b.xor(&from_runs).is_empty());
+
+        let bound = range.end - range.len() / 3;
+        let mut from_gaps = IndexSet::<2>::default();
+        for gap in a_or_b.iter_gaps(bound) {
+            assert!(gap.end <= bound && !gap.is_empty());
+            from_gaps.insert_all_in_range(gap);
+        }
+        assert_eq!(from_gaps.len() + a_or_b.iter_range(..bound).count(), bound);
+        assert!(from_gaps.is_disjoint_with(&a_or_b));
+    }
+    let mut set = IndexSet::<1>::default();
+    set.insert_all_in_range(3..500);
+    set.insert_all_in_range(640..641);
+    set.insert(1000);
+    assert_eq!(set.iter_runs().collect::<Vec<_>>(), vec![3..=499, 640..=640, 1000..=1000]);
+    assert_eq!(
+        set.iter_gaps(2000).collect::<Vec<_>>(),
+        vec![0..3, 500..640, 641..1000, 1001..2000]
+    );
+    assert_eq!(set.iter_gaps(3).collect::<Vec<_>>(), vec![0..3]);
+    assert_eq!(set.iter_gaps(600).last(), Some(500..600));
+    assert_eq!(IndexSet::<1>::default().iter_gaps(5).collect::<Vec<_>>(), vec![0..5]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_formats() {
+    use serde_test::{
+        assert_de_tokens, assert_de_tokens_error, assert_ser_tokens, assert_tokens, Configure,
+        Readable, Token,
+    };
+
+    let set = IndexSet::<2>::from_iter([1, 3, 64]);
+    assert_tokens(
+        &set.clone().readable(),
+        &[Token::Seq { len: Some(3) }, Token::U64(1), Token::U64(3), Token::U64(64), Token::SeqEnd],
+    );
+    assert_tokens(
+        &set.clone().compact(),
+        &[Token::Seq { len: Some(2) }, Token::U64(0b1010), Token::U64(1), Token::SeqEnd],
+    );
+    // trailing zero words are trimmed when serializing, and tolerated when deserializing
+    let mut trailing = IndexSet::<2>::with_chunk_capacity(9);
+    trailing.insert(0);
+    assert_ser_tokens(
+        &trailing.compact(),
+        &[Token::Seq { len: Some(1) }, Token::U64(1), Token::SeqEnd],
+    );
+    assert_de_tokens(
+        &IndexSet::<2>::from_iter([0]).compact(),
+        &[Token::Seq { len: Some(2) }, Token::U64(1), Token::U64(0), Token::SeqEnd],
+    );
+    // any ChunkRead serializes like the equivalent IndexSet
+    let other = IndexSet::<2>::from_iter([3, 100]);
+    assert_ser_tokens(
+        &set.and(&other).serializable().readable(),
+        &[Token::Seq { len: Some(1) }, Token::U64(3), Token::SeqEnd],
+    );
+    // untrusted input cannot abort the process via a huge allocation
+    assert_de_tokens_error::<Readable<IndexSet<2>>>(
+        &[Token::Seq { len: Some(1) }, Token::U64(u64::MAX), Token::SeqEnd],
+        &format!("cannot allocate an IndexSet of {} chunks", usize::MAX / 64 + 1),
+    );
+    assert_de_tokens(
+        &IndexSet::<2>::from_iter([0]).compact(),
+        &[Token::Seq { len: Some(usize::MAX) }, Token::U64(1), Token::SeqEnd],
+    );
+}
+
+#[test]
+fn binary_encoding_round_trip() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range));
+        let mut bytes = vec![];
+        a.xor(&b).write_to(&mut bytes).unwrap();
+        assert_eq!(IndexSet::<3>::read_from(&mut &bytes[..]).unwrap(), a.xor(&b).to_index_set());
+        bytes.clear();
+        a.write_to_without_checksum(&mut bytes).unwrap();
+        assert_eq!(IndexSet::<2>::read_from(&mut &bytes[..]).unwrap(), a);
+    }
+}
+
+#[test]
+fn binary_encoding_errors() {
+    use crate::encoding::DecodeError;
+
+    let mut set = IndexSet::<1>::with_chunk_capacity(4);
+    set.insert(2);
+    set.insert(70);
+    let mut bytes = vec![];
+    set.write_to(&mut bytes).unwrap();
+    let mut expected = b"CHIS\x01\x01".to_vec();
+    expected.extend(2u64.to_le_bytes());
+    expected.extend(4u64.to_le_bytes());
+    expected.extend(64u64.to_le_bytes());
+    assert_eq!(bytes[..expected.len()], expected[..]);
+    assert_eq!(bytes.len(), expected.len() + 8);
+
+    let read = |bytes: &[u8]| IndexSet::<1>::read_from(&mut &bytes[..]);
+    for len in 0..bytes.len() {
+        assert!(matches!(read(&bytes[..len]), Err(DecodeError::Truncated)));
+    }
+    let corrupt = |at: usize, value: u8| {
+        let mut bytes = bytes.clone();
+        bytes[at] = value;
+        read(&bytes)
+    };
+    assert!(matches!(corrupt(0, b'X'), Err(DecodeError::BadMagic(_))));
+    assert!(matches!(corrupt(4, 2), Err(DecodeError::UnsupportedVersion(2))));
+    assert!(matches!(corrupt(5, 3), Err(DecodeError::UnknownFlags(3))));
+    assert!(matches!(corrupt(14, 0b101), Err(DecodeError::ChecksumMismatch { .. })));
+    assert!(matches!(corrupt(13, 0xFF), Err(DecodeError::TooLarge { .. })));
+
+    let mut untrimmed = b"CHIS\x01\x00".to_vec();
+    untrimmed.extend(1u64.to_le_bytes());
+    untrimmed.extend(0u64.to_le_bytes());
+    assert!(matches!(read(&untrimmed), Err(DecodeError::NotTrimmed)));
+}
+
+#[test]
+fn range_notation_round_trip() {
+    let set: IndexSet<1> = [0, 1, 2, 3, 4, 5, 9, 63, 64, 65, 200].iter().copied().collect();
+    assert_eq!(set.range_notation().to_string(), "0-5,9,63-65,200");
+    assert_eq!(IndexSet::<1>::default().range_notation().to_string(), "");
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<2>::from_iter(stream(0, range));
+        let text = a.range_notation().to_string();
+        assert_eq!(text.parse::<IndexSet<2>>().unwrap(), a);
+    }
+
+    // sets containing Index::MAX, which only the sparse types can hold, print and parse it too
+    use crate::notation::parse_ranges;
+    const MAX: Index = Index::MAX;
+    let parsed = |text: &str| -> Vec<RangeInclusive<Index>> {
+        parse_ranges(text).unwrap().into_iter().map(|(_, range)| range).collect()
+    };
+    for indices in [vec![MAX], vec![5, MAX], vec![0, 1, MAX - 1, MAX], (MAX - 200..=MAX).collect()]
+    {
+        let roaring = RoaringIndexSet::from_iter(indices.iter().copied());
+        let paged = PagedIndexSet::from_iter(indices.iter().copied());
+        let interval = IntervalSet::from_iter(indices.iter().copied());
+        let texts = [
+            roaring.range_notation().to_string(),
+            paged.range_notation().to_string(),
+            interval.range_notation().to_string(),
+            roaring.and(&paged).range_notation().to_string(),
+        ];
+        for text in texts {
+            assert!(text.ends_with(&MAX.to_string()));
+            assert_eq!(parsed(&text), roaring.iter_runs().collect::<Vec<_>>());
+            let mut from_text = IntervalSet::new();
+            for (start, last) in parsed(&text).into_iter().map(RangeInclusive::into_inner) {
+                from_text.insert_all_in_range(start..last);
+                from_text.insert(last);
+            }
+            assert_eq!(from_text, interval);
+        }
+    }
+    assert_eq!(
+        RoaringIndexSet::from_iter([5, MAX]).range_notation().to_string(),
+        format!("5,{}", MAX)
+    );
+    assert_eq!(parsed(&format!("3..={}", MAX)), [3..=MAX]);
+}
+
+#[test]
+fn range_notation_parsing() {
+    use crate::notation::{ParseErrorKind as K, ParseIndexSetError};
+
+    let parse = |s: &str| s.parse::<IndexSet<1>>();
+    let expected: IndexSet<1> = [0, 1, 2, 5, 7, 8, 9, 100].iter().copied().collect();
+    assert_eq!(parse(" 0 -2 , 5,7..=8 ,\t9..10, 100 ..100,100\n"), Ok(expected));
+    assert_eq!(parse("  "), Ok(IndexSet::default()));
+    assert_eq!(parse("4..4"), Ok(IndexSet::default()));
+
+    let error = |pos, kind| Err(ParseIndexSetError { pos, kind });
+    assert_eq!(parse("1,,2"), error(2, K::ExpectedIndex));
+    assert_eq!(parse("1,2,"), error(4, K::ExpectedIndex));
+    assert_eq!(parse("1-"), error(2, K::ExpectedIndex));
+    assert_eq!(parse("1 2"), error(2, K::ExpectedSeparator));
+    assert_eq!(parse("1;2"), error(1, K::ExpectedSeparator));
+    assert_eq!(parse("3, 9-4"), error(3, K::DescendingRange));
+    assert_eq!(parse("3, 9..8"), error(3, K::DescendingRange));
+    assert_eq!(parse("0, 99999999999999999999999"), error(3, K::IndexOverflow));
+    let max = Index::MAX.to_string();
+    assert_eq!(parse("3, 9-7"), error(3, K::DescendingRange));
+    assert_eq!(parse(&format!("1-{}", max)), error(0, K::TooLarge));
+    assert_eq!(parse(&format!("0, {}", max)), error(3, K::TooLarge));
+    assert_eq!(parse(&format!("0, {}", Index::MAX - 1)), error(3, K::TooLarge));
+    assert_eq!(
+        parse(&format!("0, {}..{}, 1", Index::MAX, Index::MAX)),
+        Ok(IndexSet::from_iter([0, 1]))
+    );
+}
+
+#[test]
+fn operators_match_combinators() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<1>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<2>::from_iter(stream(1, range.clone()));
+        let c = IndexSet::<1>::from_iter(stream(2, range.start / 2..range.end / 2));
+
+        assert_eq!(&a | &b, a.or(&b).to_index_set());
+        assert_eq!(&a & &b, a.and(&b).to_index_set());
+        assert_eq!(&a ^ &b, a.xor(&b).to_index_set());
+        assert_eq!(&a - &b, a.without(&b).to_index_set());
+
+        let a_or_b = a.or(&b);
+        let lazy = &a_or_b - &c;
+        assert_eq!(
+            lazy.to_index_set::<1>(),
+            a.or(&b).to_index_set::<1>().without(&c).to_index_set()
+        );
+        assert_eq!(&c & &(&a.or(&b) ^ &c), c.and(&a.or(&b).xor(&c)).to_index_set());
+
+        let mut d = a.clone();
+        d |= &b;
+        assert_eq!(d, &a | &b);
+        // b may have fewer chunks than d
+        d &= &c;
+        assert_eq!(d, a.or(&b).to_index_set::<1>().and(&c).to_index_set());
+        d ^= &a.without(&c);
+        assert_eq!(d, (&(&a | &b) & &c) ^ &a.without(&c));
+        d -= &b;
+        assert!(d.and(&b).is_empty());
+        d -= &d.clone();
+        assert!(d.is_empty());
+    }
+}
+
+/// Compares sets as the integers whose bits are their elements.
+fn powerset_cmp_naive<A: ChunkRead, B: ChunkRead>(a: &A, b: &B) -> core::cmp::Ordering {
+    let chunks = |x: &dyn Fn(usize) -> Option<Chunk>, len: usize| -> Vec<Chunk> {
+        (0..len).rev().map(|i| x(i).unwrap_or(0)).collect()
+    };
+    let len = a.zero_chunks_from_exact().max(b.zero_chunks_from_exact());
+    chunks(&|i| a.get_chunk(i), len).cmp(&chunks(&|i| b.get_chunk(i), len))
+}
+
+#[test]
+fn submasks_and_supersets() {
+    let set = IndexSet::<1>::from_iter([1, 4, 63, 64, 130]);
+    let mut submasks = Submasks::<1>::new(&set);
+    let mut count = 0;
+    let mut prev: Option<IndexSet<1>> = None;
+    while let Some(sub) = submasks.next_subset() {
+        assert!(sub.is_subset_of(&set));
+        if let Some(prev) = &prev {
+            assert_eq!(powerset_cmp_naive(sub, prev), core::cmp::Ordering::Less);
+        }
+        prev = Some(sub.clone());
+        count += 1;
+    }
+    assert_eq!(count, 1 << 5);
+    assert!(prev.unwrap().is_empty());
+    assert!(submasks.next_subset().is_none());
+
+    let base = IndexSet::<1>::from_iter([2, 66, 200]);
+    let mut supersets = Supersets::<1>::new(&base, 68);
+    let mut seen = std::collections::HashSet::new();
+    while let Some(sup) = supersets.next_superset() {
+        assert!(base.is_subset_of(sup));
+        assert!(sup.iter().all(|i| i < 68 || i == 200));
+        assert!(seen.insert(sup.clone()));
+        if seen.len() > 10_000 {
+            break;
+        }
+    }
+    let mut small = Supersets::<1>::new(&base, 5);
+    let mut count = 0;
+    while small.next_superset().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 1 << 4);
+}
+
+#[test]
+fn k_subsets_in_colex_order() {
+    let binomial = |n: usize, k: usize| (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1));
+    for (n, k) in [(0, 0), (5, 0), (5, 5), (3, 4), (10, 3), (70, 2), (130, 1)] {
+        let mut subsets = KSubsets::<1>::new(n, k);
+        let mut prev: Option<IndexSet<1>> = None;
+        let mut count = 0;
+        while let Some(sub) = subsets.next_subset() {
+            assert_eq!(sub.len(), k);
+            assert!(sub.iter().all(|i| i < n));
+            if let Some(prev) = &prev {
+                assert_eq!(powerset_cmp_naive(sub, prev), core::cmp::Ordering::Greater);
+            }
+            prev = Some(sub.clone());
+            count += 1;
+        }
+        let expected = if k <= n { binomial(n, k) } else { 0 };
+        assert_eq!(count, expected, "n={} k={}", n, k);
+        assert!(subsets.next_subset().is_none());
+    }
+}
+
+#[test]
+fn subset_map_transforms() {
+    const U: usize = 10;
+    let rng = fastrand::Rng::with_seed(1509);
+    let original = SubsetMap::<i64>::from_fn(U, |_| rng.i64(-100..100));
+    let mut map = original.clone();
+    map.zeta_transform();
+    for (key, value) in map.iter() {
+        let expected: i64 =
+            original.iter().filter(|(sub, _)| sub.is_subset_of(&key)).map(|(_, v)| v).sum();
+        assert_eq!(*value, expected);
+    }
+    map.mobius_transform();
+    assert_eq!(map, original);
+
+    // entries are in the order of the powerset
+    let mut key = original.iter().next_back().unwrap().0;
+    for (expected_key, _) in original.iter().rev().skip(1) {
+        assert!(key.try_decrease_in_powerset_order());
+        assert_eq!(key, expected_key);
+    }
+    assert!(!key.try_decrease_in_powerset_order());
+}
+
+#[test]
+fn subset_map_keys() {
+    let mut map = SubsetMap::<u32>::new(5);
+    assert_eq!(map.len(), 32);
+    let key: IndexSet<1> = [0, 4].iter().copied().collect();
+    map[&key] += 3;
+    assert_eq!(map.get(&key), Some(&3));
+    // any ChunkRead is a key
+    assert_eq!(map.get(&0b10001usize), Some(&3));
+    assert_eq!(map.get(&[0b10001usize, 0][..]), Some(&3));
+    assert_eq!(map.get(&key.or(&0b1usize)), Some(&3));
+    // keys outside the universe are rejected
+    assert_eq!(map.get(&0b100000usize), None);
+    assert_eq!(map.get(&[1, 1][..]), None);
+    assert!(map.get_mut(&IndexSet::<1>::from_iter([70])).is_none());
+    assert_eq!(SubsetMap::<u8>::new(0).len(), 1);
+}
+
+#[test]
+fn dyn_chunk_reads() {
+    /// Every multiple of 3 below 300
+    struct Multiples;
+    impl ChunkRead for Multiples {
+        fn get_chunk(&self, idx_of_chunk: usize) -> Option<Chunk> {
+            let bits = usize::BITS as usize;
+            let range = idx_of_chunk * bits..(idx_of_chunk + 1) * bits;
+            (idx_of_chunk * bits < 300).then(|| {
+                range.filter(|i| i % 3 == 0 && *i < 300).fold(0, |chunk, i| chunk | 1 << (i % bits))
+            })
+        }
+        fn zero_chunks_from_conservative(&self) -> usize {
+            300 / usize::BITS as usize + 1
+        }
+    }
+    let a = IndexSet::<4>::from_iter(stream(0, 0..500));
+    let b = IndexSet::<1>::from_iter(stream(1, 0..500));
+    let slice: &[Chunk] = &[0b1011, 0, !0];
+    let readers: Vec<Box<dyn DynChunkRead + '_>> = vec![
+        Box::new(a.clone()),
+        Box::new(slice),
+        Box::new(a.or(&b)),
+        Box::new(RoaringIndexSet::from_chunk_read(&b)),
+        Box::new(Multiples),
+        Box::new(Box::new(b.ranked())),
+    ];
+    let expected: Vec<IndexSet<1>> = vec![
+        a.to_index_set(),
+        slice.to_index_set(),
+        a.or(&b).to_index_set(),
+        b.to_index_set(),
+        Multiples.to_index_set(),
+        b.to_index_set(),
+    ];
+    for (x, ex) in readers.iter().zip(expected.iter()) {
+        assert_eq!(x.to_index_set::<1>(), *ex);
+        assert_eq!(x.len(), ex.len());
+        assert_eq!(x.max_element(), ex.max_element());
+        assert_eq!(x.select(7), ex.select(7));
+        for (y, ey) in readers.iter().zip(expected.iter()) {
+            // set algebra between trait objects
+            assert_eq!(x.and(&**y).to_index_set::<1>(), ex.and(ey).to_index_set());
+            assert_eq!(x.is_subset_of(&**y), ex.is_subset_of(ey));
+            assert_eq!(&x.to_index_set::<1>() ^ y, ex.xor(ey).to_index_set());
+        }
+    }
+}
+
+#[test]
+fn many_combined_chunk_reads() {
+    use crate::combinators::{IntersectAll, UnionAll, XorAll};
+
+    let sets: Vec<IndexSet<1>> = (0..5).map(|i| IndexSet::from_iter(stream(i, 0..2000))).collect();
+    let nested = |f: fn(&IndexSet<1>, &IndexSet<1>) -> IndexSet<1>| {
+        sets[1..].iter().fold(sets[0].clone(), |acc, set| f(&acc, set))
+    };
+    let union = UnionAll::new(&sets);
+    let intersection = IntersectAll::new(&sets);
+    let xor = XorAll::new(sets.iter());
+    assert_eq!(union.to_index_set::<1>(), nested(|a, b| a | b));
+    assert_eq!(intersection.to_index_set::<1>(), nested(|a, b| a & b));
+    assert_eq!(xor.to_index_set::<1>(), nested(|a, b| a ^ b));
+    assert_eq!(intersection.iter().count(), nested(|a, b| a & b).len());
+    assert_eq!(union.zero_chunks_from_exact(), nested(|a, b| a | b).zero_chunks_from_exact());
+
+    // intersection stops at the shortest reader
+    let short = IndexSet::<1>::from_iter([3, 5]);
+    let readers: Vec<&dyn DynChunkRead> = vec![&sets[0], &short, &sets[1]];
+    let mixed = IntersectAll::new(readers.iter().copied());
+    assert_eq!(mixed.zero_chunks_from_conservative(), 1);
+    assert_eq!(mixed.get_chunk(1), None);
+    assert_eq!(mixed.to_index_set::<1>(), sets[0].and(&short).and(&sets[1]).to_index_set());
+
+    let none: [&IndexSet<1>; 0] = [];
+    assert!(UnionAll::new(none).is_empty());
+    assert!(IntersectAll::new(none).is_empty());
+}
+
+#[test]
+// map_or rather than is_none_or, which needs Rust 1.82
+#[allow(clippy::unnecessary_map_or)]
+fn complement_and_unary_ops() {
+    use crate::combinators::un_ops::{Not, Truncate};
+
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<1>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<1>::from_iter(stream(1, range.clone()));
+        for bound in [0, 1, 63, 64, 65, range.start, range.end, range.end + 200] {
+            let expected: IndexSet<1> = (0..bound).filter(|&i| !b.contains(i)).collect();
+            let complement = b.complement(bound);
+            assert_eq!(complement.to_index_set::<1>(), expected);
+            assert_eq!(complement.len(), expected.len());
+            assert!(complement.max_element().map_or(true, |max| max < bound));
+            assert_eq!(b.map_chunks(Not { bound }).to_index_set::<1>(), expected);
+            assert_eq!(
+                a.and(&complement).to_index_set::<1>(),
+                a.without(&b.map_chunks(Truncate { bound }))
+                    .to_index_set::<1>()
+                    .map_chunks(Truncate { bound })
+                    .to_index_set()
+            );
+            let truncated: IndexSet<1> = a.iter().filter(|&i| i < bound).collect();
+            assert_eq!(a.map_chunks(Truncate { bound }).to_index_set::<1>(), truncated);
+            assert_eq!(
+                a.map_chunks(Truncate { bound }).iter().collect::<Vec<_>>(),
+                truncated.iter().collect::<Vec<_>>()
+            );
+        }
+    }
+}
+
+#[test]
+fn closure_and_ternary_ops() {
+    use crate::combinators::fn_ops::{FnOp, FnOp3, ZeroWhen};
+    use crate::combinators::tern_ops::{Majority, Mux};
+
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<1>::from_iter(stream(0, range.clone()));
+        let b = IndexSet::<1>::from_iter(stream(1, range.start..range.end / 2 + 1));
+        let c = IndexSet::<1>::from_iter(stream(2, range.clone()));
+
+        let or = FnOp::new(ZeroWhen::Both, |a, b| a | b);
+        let and = FnOp::new(ZeroWhen::Either, |a, b| a & b);
+        let without = FnOp::new(ZeroWhen::Left, |a, b| a & !b);
+        let into = FnOp::new(ZeroWhen::Right, |a: Chunk, b| !a & b);
+        assert_eq!(a.combine_chunks(or, &b).to_index_set::<1>(), &a | &b);
+        assert_eq!(a.combine_chunks(and, &b).to_index_set::<1>(), &a & &b);
+        assert_eq!(b.combine_chunks(and, &a).to_index_set::<1>(), &a & &b);
+        assert_eq!(a.combine_chunks(without, &b).to_index_set::<1>(), &a - &b);
+        assert_eq!(b.combine_chunks(into, &a).to_index_set::<1>(), &a - &b);
+        assert_eq!(a.combine_chunks(into, &b).iter().count(), (&b - &a).len());
+
+        let expected: IndexSet<1> = (0..range.end)
+            .filter(|&i| [&a, &b, &c].iter().filter(|set| set.contains(i)).count() >= 2)
+            .collect();
+        assert_eq!(Majority.combine_readers3(&a, &b, &c).to_index_set::<1>(), expected);
+        let majority = FnOp3::new(|a, b, c| (a & b) | (a & c) | (b & c));
+        assert_eq!(majority.combine_readers3(&a, &b, &c).to_index_set::<1>(), expected);
+        assert_eq!(majority.combine_readers3(&c, &a, &b).iter().count(), expected.len());
+
+        let mux = Mux.combine_readers3(&a, &b, &c);
+        let expected = &(&a & &b) | &c.without(&a);
+        assert_eq!(mux.to_index_set::<1>(), expected);
+        assert_eq!(mux.iter().collect::<Vec<_>>(), expected.iter().collect::<Vec<_>>());
+        assert_eq!(Mux.combine_readers3(&a, &b, &EmptyIndexSet).to_index_set::<1>(), &a & &b);
+    }
+}
+
+#[test]
+fn shifted_views_and_in_place_shifts() {
+    for range in RANGES.iter().cloned() {
+        let a = IndexSet::<1>::from_iter(stream(0, range.clone()));
+        for k in [0, 1, 5, 63, 64, 65, 128, 200, range.end] {
+            let up: IndexSet<1> = a.iter().map(|i| i + k).collect();
+            let down: IndexSet<1> = a.iter().filter(|&i| i >= k).map(|i| i - k).collect();
+
+            let shifted = a.shifted_by(k);
+            assert_eq!(shifted.to_index_set::<1>(), up);
+            assert_eq!(shifted.iter().collect::<Vec<_>>(), up.iter().collect::<Vec<_>>());
+            assert_eq!(
+                shifted.iter().rev().collect::<Vec<_>>(),
+                up.iter().rev().collect::<Vec<_>>()
+            );
+            let shifted = a.shifted_down_by(k);
+            assert_eq!(shifted.to_index_set::<1>(), down);
+            assert_eq!(shifted.iter().collect::<Vec<_>>(), down.iter().collect::<Vec<_>>());
+            assert_eq!(a.shifted_by(k).shifted_down_by(k).to_index_set::<1>(), a);
+
+            let mut b = a.clone();
+            b.enable_summary();
+            b.shift_left(k);
+            assert_eq!(b, up);
+            assert_eq!(b.iter().collect::<Vec<_>>(), up.iter().collect::<Vec<_>>());
+            b.shift_right(k);
+            assert_eq!(b, a);
+            b.shift_right(k);
+            assert_eq!(b, down);
+            assert_eq!(b.iter().collect::<Vec<_>>(), down.iter().collect::<Vec<_>>());
+        }
+    }
+    assert!(EmptyIndexSet.shifted_by(Index::MAX).is_empty());
+    assert!(IndexSet::<1>::from_iter([3]).shifted_down_by(Index::MAX).is_empty());
+
+    // shifting by nearly Index::MAX needs more chunks than can be allocated, so panics cleanly
+    let mut huge = IndexSet::<1>::from_iter([3, 500]);
+    let shift = std::panic::AssertUnwindSafe(|| huge.shift_left(Index::MAX - 10));
+    assert!(std::panic::catch_unwind(shift).is_err());
+    assert_eq!(huge, IndexSet::<1>::from_iter([3, 500]));
+    let mut bytes = IndexSet::<1, u8>::from_iter([3]);
+    let shift = std::panic::AssertUnwindSafe(|| bytes.shift_left(Index::MAX - 3));
+    assert!(std::panic::catch_unwind(shift).is_err());
+    assert_eq!(bytes.iter().collect::<Vec<_>>(), [3]);
+}
+
+#[test]
+fn sumsets_and_subset_sums() {
+    let rng = fastrand::Rng::with_seed(2021);
+    for _ in 0..20 {
+        let a: IndexSet<1> = (0..rng.usize(0..20)).map(|_| rng.usize(0..300)).collect();
+        let b: IndexSet<2> = (0..rng.usize(0..60)).map(|_| rng.usize(0..300)).collect();
+        let bound = rng.usize(0..700);
+        let expected: IndexSet<1> =
+            a.iter().flat_map(|i| b.iter().map(move |j| i + j)).filter(|&s| s < bound).collect();
+        assert_eq!(sumset::<1, _, _>(&a, &b, bound), expected);
+        assert_eq!(sumset::<1, _, _>(&b, &a, bound), expected);
+        assert_eq!(
+            sumset::<1, _, _>(&a.or(&EmptyIndexSet), &[0b1usize][..], bound),
+            a.and(&(0..bound).collect::<IndexSet<1>>()).to_index_set()
+        );
+
+        let weights: Vec<usize> = (0..rng.usize(0..8)).map(|_| rng.usize(1..100)).collect();
+        let mut expected: IndexSet<1> = IndexSet::default();
+        for mask in 0usize..1 << weights.len() {
+            let total: usize = weights
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask >> i & 1 == 1)
+                .map(|(_, w)| w)
+                .sum();
+            // the initial element 0 is kept, regardless of bound
+            if total < bound || mask == 0 {
+                expected.insert(total);
+            }
+        }
+        let mut reachable = IndexSet::<1>::from_iter([0]);
+        reachable.enable_summary();
+        reachable.subset_sum_reachability(weights.iter().copied(), bound);
+        assert_eq!(reachable, expected);
+        assert_eq!(reachable.iter().collect::<Vec<_>>(), expected.iter().collect::<Vec<_>>());
+    }
+    let mut reachable = IndexSet::<1>::from_iter([0, 9]);
+    reachable.subset_sum_reachability([1, 2], 0);
+    assert_eq!(reachable, IndexSet::from_iter([0, 9]));
+    assert!(sumset::<1, _, _>(&reachable, &reachable, 0).is_empty());
+
+    // the result is sized by its greatest possible sum, not by a huge bound
+    let (a, b) = (IndexSet::<1>::from_iter([1, 2]), IndexSet::<1>::from_iter([3]));
+    let small = sumset::<1, _, _>(&a, &b, Index::MAX);
+    assert_eq!(small, IndexSet::from_iter([4, 5]));
+    assert_eq!(small.chunk_capacity(), 1);
+    assert!(sumset::<1, _, _>(&EmptyIndexSet, &reachable, Index::MAX).is_empty());
+}
+
+fn check_word_width<W: Word>(rng: &fastrand::Rng) {
+    use std::hash::BuildHasher;
+    let elems = |s: &dyn DynChunkRead| s.iter().collect::<Vec<_>>();
+    for _ in 0..20 {
+        let mut a: IndexSet<1, W> = IndexSet::default();
+        let mut expected: IndexSet<1> = IndexSet::default();
+        if rng.bool() {
+            a.enable_summary();
+        }
+        for _ in 0..rng.usize(0..40) {
+            let i = rng.usize(0..400);
+            assert_eq!(a.insert(i), expected.insert(i));
+        }
+        let range = RANGES[rng.usize(0..RANGES.len())].clone();
+        a.insert_all_in_range(range.clone());
+        expected.insert_all_in_range(range);
+        for _ in 0..rng.usize(0..20) {
+            let i = rng.usize(0..400);
+            assert_eq!(a.remove(i), expected.remove(i));
+        }
+        let range = RANGES[rng.usize(0..RANGES.len())].clone();
+        a.remove_all_in_range(range.clone());
+        expected.remove_all_in_range(range);
+        assert_eq!(a.set_cmp(&expected), Some(core::cmp::Ordering::Equal));
+        assert_eq!(elems(&a), elems(&expected));
+        assert_eq!(elems(&a.iter().rev().collect::<IndexSet<1>>()), elems(&expected));
+        assert_eq!(a.len(), expected.len());
+        assert_eq!(IndexSet::<1, W>::from_chunk_read(&expected), a);
+        assert_eq!(a.to_index_set::<1>(), expected);
+
+        let b: IndexSet<2> = (0..rng.usize(0..40)).map(|_| rng.usize(0..400)).collect();
+        assert_eq!(elems(&(&a | &b)), elems(&(&expected | &b)));
+        assert_eq!(elems(&(&a & &b)), elems(&(&expected & &b)));
+        assert_eq!(elems(&(a.clone() ^ &b)), elems(&(&expected ^ &b)));
+        assert_eq!(elems(&(a.clone() - &b)), elems(&(&expected - &b)));
+        let mut c = a.clone();
+        c.insert_all(&b);
+        assert_eq!(elems(&c), elems(&(&expected | &b)));
+        c.remove_all(&b);
+        assert_eq!(elems(&c), elems(&(&expected - &b)));
+
+        let k = rng.usize(0..150);
+        let mut c = a.clone();
+        c.shift_left(k);
+        assert_eq!(elems(&c), elems(&expected.shifted_by(k)));
+        c.shift_right(k);
+        assert_eq!(c, a);
+
+        let mut c = a.clone();
+        let mut d = expected.clone();
+        for _ in 0..3 {
+            c.try_increase_in_powerset_order();
+            d.try_increase_in_powerset_order();
+            assert_eq!(elems(&c), elems(&d));
+        }
+        c.shrink_to_fit();
+        assert_eq!(elems(&c), elems(&d));
+    }
+    let mut a: IndexSet<2, W> = IndexSet::default();
+    assert!(!a.try_decrease_in_powerset_order());
+    assert!(a.try_increase_in_powerset_order());
+    assert_eq!(elems(&a), [0]);
+    a.insert(1000);
+    a.remove(1000);
+    let b: IndexSet<2, W> = [0].into_iter().collect();
+    assert_eq!(a, b);
+    let state = std::hash::RandomState::new();
+    assert_eq!(state.hash_one(&a), state.hash_one(&b));
+}
+
+#[test]
+fn word_widths() {
+    let rng = fastrand::Rng::with_seed(2022);
+    check_word_width::<u8>(&rng);
+    check_word_width::<u16>(&rng);
+    check_word_width::<u32>(&rng);
+    check_word_width::<u64>(&rng);
+    check_word_width::<u128>(&rng);
+    check_word_width::<usize>(&rng);
+
+    let a: IndexSet<3, u8> = "3-10,17".parse().unwrap();
+    assert_eq!(a.as_words(), &[0b1111_1000, 0b0000_0111, 0b0000_0010]);
+    assert_eq!(a.word_capacity(), 3);
+    assert_eq!(a.capacity(), ..24);
+    assert_eq!(a.get_chunk(0), Some(0b10_0000_0111_1111_1000));
+    assert_eq!(a.zero_chunks_from_conservative(), 1);
+    let b = IndexSet::<1, u128>::from_word_slice(&[1 << 127]);
+    assert_eq!(b.iter().collect::<Vec<_>>(), [127]);
+    assert_eq!(
+        b.next_nonzero_chunk_from(0),
+        Some((127 / usize::BITS as usize, 1 << (127 % usize::BITS)))
+    );
+
+    // word indices and capacities count u8 words, while ChunkRead counts Chunks
+    const B: usize = usize::BITS as usize;
+    let mut c = IndexSet::<1, u8>::with_word_capacity(3);
+    assert_eq!((c.word_capacity(), c.capacity()), (3, ..24));
+    c.insert(2 * B + 9);
+    assert!(c.word_capacity() > (2 * B + 9) / 8);
+    assert_eq!(c.capacity(), ..c.word_capacity() * 8);
+    assert_eq!(c.as_words()[(2 * B + 9) / 8], 1 << 1);
+    assert_eq!(c.next_nonzero_chunk_from(0), Some((2, 1 << 9)));
+    assert_eq!((c.get_chunk(1), c.get_chunk(2)), (Some(0), Some(1 << 9)));
+    c.as_words_mut()[1] = 0b1000_0001;
+    assert_eq!(c.iter().collect::<Vec<_>>(), [8, 15, 2 * B + 9]);
+    assert_eq!(c.get_chunk(0), Some(0b1000_0001 << 8));
+    let d = IndexSet::<1>::from_chunk_slice(&[0b1000_0001 << 8, 0, 1 << 9]);
+    assert_eq!(c, IndexSet::<1, u8>::from_chunk_read(&d));
+    assert_eq!(c.to_index_set::<1>(), d);
+    assert!(c.xor(&d).is_empty());
+    let mut e = IndexSet::<1, u8>::from_chunk_read(&d);
+    e.resize_words_to_accomodate(100);
+    assert!(e.word_capacity() > 100);
+    assert_eq!(e.zero_chunks_from_conservative(), e.word_capacity().div_ceil(8));
+    assert_eq!(e, c);
+}
+
+#[test]
+fn bit_arrays() {
+    type Small = BitArray<70, { chunk_count_for(70) }>;
+    const PRIMES: Small = Small::new().with(2).with(3).with(5).with(7).with(67);
+    const ODD_PRIMES: Small = PRIMES.without(2);
+    static NON_PRIMES: Small = PRIMES.inverted();
+    assert_eq!(chunk_count_for(0), 0);
+    assert_eq!(chunk_count_for(70), 2);
+    assert_eq!(PRIMES.iter().collect::<Vec<_>>(), [2, 3, 5, 7, 67]);
+    assert_eq!(ODD_PRIMES.iter().collect::<Vec<_>>(), [3, 5, 7, 67]);
+    assert!(PRIMES.contains(67) && !PRIMES.contains(68) && !PRIMES.contains(1000));
+    let describe = |set: Small| match set {
+        PRIMES => "primes",
+        ODD_PRIMES => "odd primes",
+        _ => "other",
+    };
+    assert_eq!(describe(ODD_PRIMES.with(2)), "primes");
+    assert_eq!(describe(Small::full()), "other");
+
+    assert_eq!(Small::full().len(), 70);
+    assert_eq!(Small::full().max_element(), Some(69));
+    assert_eq!(NON_PRIMES.len(), 65);
+    assert_eq!(NON_PRIMES.union(PRIMES), Small::full());
+    assert_eq!(!!PRIMES, PRIMES);
+    assert_eq!(!Small::new(), Small::full());
+    assert_eq!(BitArray::<128, 2>::full().as_chunks(), &[!0; 2]);
+    assert!(BitArray::<0, 0>::full().is_empty());
+
+    let rng = fastrand::Rng::with_seed(2023);
+    for _ in 0..20 {
+        let a: Small = (0..rng.usize(0..30)).map(|_| rng.usize(0..70)).collect();
+        let b: Small = (0..rng.usize(0..30)).map(|_| rng.usize(0..70)).collect();
+        let a_set = a.to_index_set::<1>();
+        assert_eq!((a | b).to_index_set::<1>(), &a_set | &b);
+        assert_eq!((a & b).to_index_set::<1>(), &a_set & &b);
+        assert_eq!((a ^ b).to_index_set::<1>(), &a_set ^ &b);
+        assert_eq!((a - b).to_index_set::<1>(), &a_set - &b);
+        assert_eq!(Small::from_chunks(*a.as_chunks()), a);
+    }
+
+    let mut a = Small::default();
+    assert_eq!(a.try_insert(69), Ok(true));
+    assert_eq!(a.try_insert(69), Ok(false));
+    let e = a.try_insert(70).unwrap_err();
+    assert_eq!(e, IndexOutOfBounds { index: 70, bits: 70 });
+    assert_eq!(e.to_string(), "index 70 is out of bounds of BitArray with 70 bits");
+    assert!(!a.remove(70));
+    assert!(a.remove(69));
+    assert!(a.is_empty());
+    assert!(std::panic::catch_unwind(|| Small::new().insert(70)).is_err());
+    assert!(std::panic::catch_unwind(|| Small::from_chunks([0, 1 << 6])).is_err());
+}
+
+#[test]
+fn const_index_sets() {
+    const A: BitArray<64, 1> = const_index_set![0..8, 12, 40..=45];
+    static B: BitArray<200, { chunk_count_for(200) }> = const_index_set![60..130, 199, 5..=5,];
+    const EMPTY: BitArray<10, 1> = const_index_set![];
+    assert_eq!(A.range_notation().to_string(), "0-7,12,40-45");
+    assert_eq!(B.range_notation().to_string(), "5,60-129,199");
+    assert!(EMPTY.is_empty());
+    let (hi, lo) = (9, 3);
+    assert_eq!(
+        const_index_set![100; 3, 99, hi..lo, hi - 2..=lo + 3].iter().collect::<Vec<_>>(),
+        [3, 99]
+    );
+    let b = const_index_set![200; 60..130, 199, 5];
+    assert_eq!(b, B);
+    assert_eq!(B.or(&A).to_index_set::<1>(), "0-7,12,40-45,60-129,199".parse().unwrap());
+
+    const C: BitArray<130, 3> = BitArray::from_indices(&[0, 64, 129]);
+    assert_eq!(C.as_chunks(), &[1, 1, 2]);
+    assert_eq!(BitArray::<130, 3>::from_range(0..130), BitArray::full());
+    for start in 0..130 {
+        for end in start..=130 {
+            let expected: BitArray<130, 3> = (start..end).collect();
+            assert_eq!(BitArray::from_range(start..end), expected);
+        }
+    }
+    assert!(BitArray::<130, 3>::from_range(hi..lo).is_empty());
+    assert!(std::panic::catch_unwind(|| BitArray::<130, 3>::from_range(0..131)).is_err());
+    assert!(std::panic::catch_unwind(|| BitArray::<130, 3>::from_indices(&[130])).is_err());
+}
+
+#[test]
+fn typed_index_sets() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    struct NodeId(u32);
+    impl Idx for NodeId {
+        fn to_index(self) -> Index {
+            self.0 as Index
+        }
+        fn from_index(index: Index) -> Self {
+            NodeId(index as u32)
+        }
+    }
+    let mut a: TypedIndexSet<NodeId, 1> = [NodeId(3), NodeId(70)].into_iter().collect();
+    assert!(a.insert(NodeId(5)));
+    assert!(!a.insert(NodeId(5)));
+    assert!(a.contains(NodeId(70)) && !a.contains(NodeId(4)));
+    assert_eq!(a.iter().collect::<Vec<_>>(), [NodeId(3), NodeId(5), NodeId(70)]);
+    assert_eq!(a.iter().next_back(), Some(NodeId(70)));
+    assert_eq!((a.min_element(), a.max_element()), (Some(NodeId(3)), Some(NodeId(70))));
+    assert_eq!(format!("{:?}", a), "{NodeId(3), NodeId(5), NodeId(70)}");
+    assert_eq!(a.as_raw().range_notation().to_string(), "3,5,70");
+
+    let b: TypedIndexSet<NodeId, 1> = [NodeId(5), NodeId(6)].into_iter().collect();
+    assert_eq!((&a | &b).iter().map(|id| id.0).collect::<Vec<_>>(), [3, 5, 6, 70]);
+    assert_eq!((&a & &b).iter().map(|id| id.0).collect::<Vec<_>>(), [5]);
+    assert_eq!((&a ^ &b).iter().map(|id| id.0).collect::<Vec<_>>(), [3, 6, 70]);
+    assert_eq!((&a - &b).iter().map(|id| id.0).collect::<Vec<_>>(), [3, 70]);
+    assert!(!a.is_disjoint_with(&b) && (&a & &b).is_subset_of(&b));
+
+    let mut c = a.clone();
+    c |= &b;
+    assert!(c.is_superset_of(&a) && c.is_superset_of(&b));
+    c.remove_all(&b);
+    assert_eq!(c, &a - &b);
+    c.insert_all(&b);
+    c -= &a;
+    assert!(c.remove(NodeId(6)));
+    c.extend([NodeId(1)]);
+    assert_eq!(c.len(), 1);
+    c.clear();
+    assert!(c.is_empty());
+
+    // raw interop
+    let raw = a.clone().into_raw();
+    assert!(a.as_raw().is_subset_of(&raw.or(&[0b1usize][..])));
+    let mut d = TypedIndexSet::<NodeId, 1>::from_raw(raw);
+    d.as_raw_mut().insert(0);
+    assert_eq!(d.min_element(), Some(NodeId(0)));
+    let plain: TypedIndexSet<usize, 2> = (0..10).collect();
+    assert_eq!(plain.len(), 10);
+}
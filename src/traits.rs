@@ -1,4 +1,5 @@
 use super::*;
+use core::ops::RangeBounds;
 
 use crate::combinators::bin_ops::*;
 
@@ -12,6 +13,17 @@ pub trait ChunkRead {
     /// Return an index s.t. get_chunk(x) for x in idx_of_chunk.. will return None. Prioritize speed over accuracy.
     fn zero_chunks_from_conservative(&self) -> usize;
     ///////
+    /// Return the index and value of the first nonzero chunk at or after idx_of_chunk, if there is one.
+    /// Implementors that know where their nonzero chunks are should override this to skip runs of zero chunks.
+    fn next_nonzero_chunk_from(&self, mut idx_of_chunk: usize) -> Option<(usize, Chunk)> {
+        loop {
+            let chunk = self.get_chunk(idx_of_chunk)?;
+            if chunk != 0 {
+                return Some((idx_of_chunk, chunk));
+            }
+            idx_of_chunk += 1;
+        }
+    }
     fn zero_chunks_from_exact(&self) -> usize {
         // scan from back to front looking for 1st nonzero chunk
         let mut at = self.zero_chunks_from_conservative();
@@ -33,39 +45,68 @@ pub trait ChunkRead {
         }
         me
     }
-    fn is_subset_of<A: ChunkRead>(&self, other: &A) -> bool {
+    fn is_subset_of<A: ChunkRead + ?Sized>(&self, other: &A) -> bool {
         use core::cmp::Ordering::*;
         match self.set_cmp(other) {
             Some(Equal | Less) => true,
             Some(Greater) | None => false,
         }
     }
-    fn is_superset_of<A: ChunkRead>(&self, other: &A) -> bool {
+    fn is_superset_of<A: ChunkRead + ?Sized>(&self, other: &A) -> bool {
         use core::cmp::Ordering::*;
         match self.set_cmp(other) {
             Some(Equal | Greater) => true,
             Some(Less) | None => false,
         }
     }
-    fn is_disjoint_with<A: ChunkRead>(&self, other: &A) -> bool {
+    fn is_disjoint_with<A: ChunkRead + ?Sized>(&self, other: &A) -> bool {
         self.combine_chunks(And, other).is_empty()
     }
-    fn chunk_list_cmp<A: ChunkRead>(&self, other: &A) -> core::cmp::Ordering {
-        use core::cmp::Ordering::*;
-        let chunks_iter = self.iter_chunks().chain(std::iter::repeat(0)).zip(other.iter_chunks());
-        chunks_iter.map(|(a, b)| a.cmp(&b)).filter(|&ord| ord != Equal).next().unwrap_or(Equal)
+    /// Compares the sequences of chunks lexicographically, treating missing chunks as zero.
+    fn chunk_list_cmp<A: ChunkRead + ?Sized>(&self, other: &A) -> core::cmp::Ordering {
+        // only chunks which are nonzero in either can differ
+        let mut idx_of_chunk = 0;
+        while let Some(idx) = next_nonzero_chunk_in_either(self, other, idx_of_chunk) {
+            let s = self.get_chunk(idx).unwrap_or(0);
+            let o = other.get_chunk(idx).unwrap_or(0);
+            if s != o {
+                return s.cmp(&o);
+            }
+            idx_of_chunk = idx + 1;
+        }
+        core::cmp::Ordering::Equal
     }
-    fn set_cmp<A: ChunkRead>(&self, other: &A) -> Option<core::cmp::Ordering> {
+    /// Compares the sets by the powerset order (see IndexSet::try_decrease_in_powerset_order),
+    /// i.e., as the integers whose bits are their elements.
+    fn powerset_cmp<A: ChunkRead + ?Sized>(&self, other: &A) -> core::cmp::Ordering {
+        // the greatest differing chunk decides
         let mut ord = core::cmp::Ordering::Equal;
-        use core::cmp::Ordering as O;
-        for idx_of_chunk in 0.. {
-            let s = self.get_chunk(idx_of_chunk);
-            let o = other.get_chunk(idx_of_chunk);
-            if s.is_none() && o.is_none() {
-                return Some(ord);
+        let mut idx_of_chunk = 0;
+        while let Some(idx) = next_nonzero_chunk_in_either(self, other, idx_of_chunk) {
+            let s = self.get_chunk(idx).unwrap_or(0);
+            let o = other.get_chunk(idx).unwrap_or(0);
+            if s != o {
+                ord = s.cmp(&o);
             }
-            let s = s.unwrap_or(0);
-            let o = o.unwrap_or(0);
+            idx_of_chunk = idx + 1;
+        }
+        ord
+    }
+    /// Returns the position of this set in the powerset order, as a little-endian sequence of chunks
+    /// without trailing zero chunks. Invert with IndexSet::from_powerset_rank.
+    fn powerset_rank(&self) -> Vec<Chunk> {
+        (0..self.zero_chunks_from_exact())
+            .map(|idx_of_chunk| self.get_chunk(idx_of_chunk).unwrap_or(0))
+            .collect()
+    }
+    fn set_cmp<A: ChunkRead + ?Sized>(&self, other: &A) -> Option<core::cmp::Ordering> {
+        let mut ord = core::cmp::Ordering::Equal;
+        use core::cmp::Ordering as O;
+        // only chunks which are nonzero in either can differ
+        let mut idx_of_chunk = 0;
+        while let Some(idx) = next_nonzero_chunk_in_either(self, other, idx_of_chunk) {
+            let s = self.get_chunk(idx).unwrap_or(0);
+            let o = other.get_chunk(idx).unwrap_or(0);
 
             let snoto = s & !o != 0;
             if snoto {
@@ -83,23 +124,59 @@ pub trait ChunkRead {
                     O::Greater => return None,
                 };
             }
+            idx_of_chunk = idx + 1;
         }
-        unreachable!()
+        Some(ord)
     }
     fn is_empty(&self) -> bool {
-        self.iter_chunks().all(|chunk| chunk == 0)
+        self.next_nonzero_chunk_from(0).is_none()
     }
-    fn displayable(&self) -> DisplayableIndexSet<Self> {
+    fn displayable(&self) -> DisplayableIndexSet<'_, Self> {
         DisplayableIndexSet(self)
     }
-    fn iter(&self) -> IndexIter<Self> {
+    /// Returns a wrapper displaying the elements compactly in range notation, e.g., `0-5,9,12-20`.
+    /// IndexSet implements FromStr for the same notation.
+    fn range_notation(&self) -> RangeNotation<'_, Self> {
+        RangeNotation(self)
+    }
+    /// Returns a wrapper implementing serde::Serialize, in the same format as IndexSet.
+    #[cfg(feature = "serde")]
+    fn serializable(&self) -> crate::SerializableIndexSet<'_, Self> {
+        crate::SerializableIndexSet(self)
+    }
+    /// Writes the elements in the portable binary format documented in the encoding module, with a checksum.
+    /// The result can be read back with IndexSet::read_from.
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        encoding::write_chunks(self, writer, true)
+    }
+    /// Like write_to, but omits the checksum.
+    fn write_to_without_checksum<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        encoding::write_chunks(self, writer, false)
+    }
+    fn iter(&self) -> IndexIter<'_, Self> {
         IndexIter::new(self)
     }
-    fn iter_chunks(&self) -> ChunkIter<Self> {
+    /// Iterates over the elements in the given range, in ascending order (or descending, via rev).
+    fn iter_range<R: RangeBounds<Index>>(&self, range: R) -> IndexIter<'_, Self> {
+        IndexIter::with_range(self, range)
+    }
+    /// Iterates over the maximal runs of consecutive elements as inclusive ranges, in ascending order.
+    /// Works a chunk at a time, so long runs are cheap.
+    fn iter_runs(&self) -> RunIter<'_, Self> {
+        RunIter::new(self)
+    }
+    /// Iterates over the maximal runs of consecutive non-elements in 0..bound, in ascending order.
+    fn iter_gaps(&self, bound: Index) -> GapIter<'_, Self> {
+        GapIter::new(self, bound)
+    }
+    fn iter_chunks(&self) -> ChunkIter<'_, Self> {
         ChunkIter::new(self)
     }
+    fn iter_nonzero_chunks(&self) -> NonzeroChunkIter<'_, Self> {
+        NonzeroChunkIter::new(self)
+    }
     fn len(&self) -> usize {
-        self.iter_chunks().map(|chunk: Chunk| chunk.count_ones() as usize).sum()
+        self.iter_nonzero_chunks().map(|(_, chunk)| chunk.count_ones() as usize).sum()
     }
     fn buffer_chunks_into(&self, buf: &mut Vec<Chunk>) {
         for chunk in self.iter_chunks() {
@@ -113,11 +190,11 @@ pub trait ChunkRead {
             Some(chunk) => chunk & cba.chunk_mask() != 0,
         }
     }
-    fn combine_chunks<'a, B: ChunkRead, O: BinChunkOp>(
+    fn combine_chunks<'a, B: ChunkRead + ?Sized, O: BinChunkOp>(
         &'a self,
         op: O,
         b: &'a B,
-    ) -> CombinedChunkReads<Self, B, O> {
+    ) -> CombinedChunkReads<'a, Self, B, O> {
         CombinedChunkReads { a: self, b, op }
     }
     fn max_element(&self) -> Option<Index> {
@@ -133,25 +210,134 @@ pub trait ChunkRead {
         None
     }
     fn min_element(&self) -> Option<Index> {
-        for idx_of_chunk in 0.. {
-            let chunk = self.get_chunk(idx_of_chunk)?;
-            if chunk != 0 {
-                let idx_in_chunk = chunk.trailing_zeros();
+        let (idx_of_chunk, chunk) = self.next_nonzero_chunk_from(0)?;
+        let idx_in_chunk = chunk.trailing_zeros();
+        Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx())
+    }
+    /// Returns the smallest element >= bit_idx, if any.
+    fn next_index_from(&self, bit_idx: Index) -> Option<Index> {
+        let cba = ChunkBitAddr::from_bit_idx(bit_idx);
+        let masked = self.get_chunk(cba.idx_of_chunk)? & cba.chunk_mask_from();
+        let (idx_of_chunk, chunk) = if masked != 0 {
+            (cba.idx_of_chunk, masked)
+        } else {
+            self.next_nonzero_chunk_from(cba.idx_of_chunk + 1)?
+        };
+        Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk: chunk.trailing_zeros() }.to_bit_idx())
+    }
+    /// Returns the largest element < bit_idx, if any.
+    fn prev_index_before(&self, bit_idx: Index) -> Option<Index> {
+        let cba = ChunkBitAddr::from_bit_idx(bit_idx.checked_sub(1)?);
+        // chunks from zero_chunks_from_conservative() onwards are all zero; start before them.
+        let zcf = self.zero_chunks_from_conservative();
+        let (mut idx_of_chunk, mut mask) = if cba.idx_of_chunk < zcf {
+            (cba.idx_of_chunk, cba.chunk_mask_through())
+        } else {
+            (zcf.checked_sub(1)?, !0)
+        };
+        loop {
+            let masked = self.get_chunk(idx_of_chunk).unwrap_or(0) & mask;
+            if masked != 0 {
+                let idx_in_chunk = usize::BITS - 1 - masked.leading_zeros();
                 return Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx());
             }
+            idx_of_chunk = idx_of_chunk.checked_sub(1)?;
+            mask = !0;
+        }
+    }
+    /// Returns the smallest non-element >= bit_idx.
+    /// Returns None only if every index in bit_idx..=Index::MAX is an element.
+    fn next_absent_from(&self, bit_idx: Index) -> Option<Index> {
+        let cba = ChunkBitAddr::from_bit_idx(bit_idx);
+        let (mut idx_of_chunk, mut mask) = (cba.idx_of_chunk, cba.chunk_mask_from());
+        loop {
+            let absent = !self.get_chunk(idx_of_chunk).unwrap_or(0) & mask;
+            if absent != 0 {
+                let idx_in_chunk = absent.trailing_zeros();
+                return Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx());
+            }
+            if idx_of_chunk == Index::MAX / usize::BITS as usize {
+                // this was the last chunk
+                return None;
+            }
+            idx_of_chunk += 1;
+            mask = !0;
+        }
+    }
+    /// Returns the largest non-element < bit_idx, if any.
+    fn prev_absent_before(&self, bit_idx: Index) -> Option<Index> {
+        let cba = ChunkBitAddr::from_bit_idx(bit_idx.checked_sub(1)?);
+        let (mut idx_of_chunk, mut mask) = (cba.idx_of_chunk, cba.chunk_mask_through());
+        loop {
+            let absent = !self.get_chunk(idx_of_chunk).unwrap_or(0) & mask;
+            if absent != 0 {
+                let idx_in_chunk = usize::BITS - 1 - absent.leading_zeros();
+                return Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx());
+            }
+            idx_of_chunk = idx_of_chunk.checked_sub(1)?;
+            mask = !0;
+        }
+    }
+    /// Returns the number of elements < bit_idx.
+    fn rank(&self, bit_idx: Index) -> usize {
+        let cba = ChunkBitAddr::from_bit_idx(bit_idx);
+        let mut rank = 0;
+        for (idx_of_chunk, chunk) in self.iter_nonzero_chunks() {
+            if cba.idx_of_chunk < idx_of_chunk {
+                break;
+            } else if cba.idx_of_chunk == idx_of_chunk {
+                rank += (chunk & !cba.chunk_mask_from()).count_ones() as usize;
+                break;
+            }
+            rank += chunk.count_ones() as usize;
+        }
+        rank
+    }
+    /// Returns the element which has exactly k smaller elements, if any.
+    /// Inverse of rank: for every element i, select(rank(i)) == Some(i).
+    fn select(&self, mut k: usize) -> Option<Index> {
+        for (idx_of_chunk, chunk) in self.iter_nonzero_chunks() {
+            let ones = chunk.count_ones() as usize;
+            if k < ones {
+                let idx_in_chunk = select_in_chunk(chunk, k);
+                return Some(ChunkBitAddr { idx_of_chunk, idx_in_chunk }.to_bit_idx());
+            }
+            k -= ones;
         }
         None
     }
-    fn or<'a, B: ChunkRead>(&'a self, b: &'a B) -> CombinedChunkReads<Self, B, Or> {
+    /// Returns a view of self with an attached RankDirectory, accelerating rank and select.
+    fn ranked(&self) -> Ranked<'_, Self> {
+        Ranked::new(self)
+    }
+    fn map_chunks<O: UnChunkOp>(&self, op: O) -> MappedChunkReads<'_, Self, O> {
+        MappedChunkReads { a: self, op }
+    }
+    /// Returns a view of the indices in 0..bound which are not elements.
+    fn complement(&self, bound: Index) -> Complement<'_, Self> {
+        Complement { inner: self, bound }
+    }
+    /// Returns a view of the elements, each increased by `by`.
+    fn shifted_by(&self, by: Index) -> ShiftedUp<'_, Self> {
+        ShiftedUp { a: self, by }
+    }
+    /// Returns a view of the elements, each decreased by `by`, dropping those less than `by`.
+    fn shifted_down_by(&self, by: Index) -> ShiftedDown<'_, Self> {
+        ShiftedDown { a: self, by }
+    }
+    fn or<'a, B: ChunkRead + ?Sized>(&'a self, b: &'a B) -> CombinedChunkReads<'a, Self, B, Or> {
         self.combine_chunks(Or, b)
     }
-    fn xor<'a, B: ChunkRead>(&'a self, b: &'a B) -> CombinedChunkReads<Self, B, Xor> {
+    fn xor<'a, B: ChunkRead + ?Sized>(&'a self, b: &'a B) -> CombinedChunkReads<'a, Self, B, Xor> {
         self.combine_chunks(Xor, b)
     }
-    fn and<'a, B: ChunkRead>(&'a self, b: &'a B) -> CombinedChunkReads<Self, B, And> {
+    fn and<'a, B: ChunkRead + ?Sized>(&'a self, b: &'a B) -> CombinedChunkReads<'a, Self, B, And> {
         self.combine_chunks(And, b)
     }
-    fn without<'a, B: ChunkRead>(&'a self, b: &'a B) -> CombinedChunkReads<Self, B, Without> {
+    fn without<'a, B: ChunkRead + ?Sized>(
+        &'a self,
+        b: &'a B,
+    ) -> CombinedChunkReads<'a, Self, B, Without> {
         self.combine_chunks(Without, b)
     }
 }
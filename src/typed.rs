@@ -0,0 +1,160 @@
+use super::{ChunkRead, Index, IndexSet};
+use core::fmt::{self, Debug};
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
+
+/// A type of ID which is stored as an Index, e.g., a `NodeId(usize)` newtype.
+/// Requires `I::from_index(i.to_index()) == i` for every i, and vice versa for every stored index.
+pub trait Idx: Copy {
+    fn to_index(self) -> Index;
+    fn from_index(index: Index) -> Self;
+}
+
+/// IndexSet of IDs of type I, rather than of indices. Each ID is stored as its to_index().
+/// Methods take and yield I, and combine only sets with the same I.
+/// The underlying IndexSet (and thus ChunkRead) is available via as_raw etc. for interop with untyped sets.
+pub struct TypedIndexSet<I: Idx, const N: usize> {
+    raw: IndexSet<N>,
+    // fn() -> I, s.t. the set is Send, Sync, etc. regardless of I
+    phantom: PhantomData<fn() -> I>,
+}
+
+///////////////////////////////////////////////////////////////////////
+
+impl Idx for Index {
+    fn to_index(self) -> Index {
+        self
+    }
+    fn from_index(index: Index) -> Self {
+        index
+    }
+}
+
+impl<I: Idx, const N: usize> TypedIndexSet<I, N> {
+    pub fn new() -> Self {
+        Self::from_raw(IndexSet::default())
+    }
+    /// Treats each index of raw as the ID I::from_index(index).
+    pub fn from_raw(raw: IndexSet<N>) -> Self {
+        Self { raw, phantom: PhantomData }
+    }
+    pub fn as_raw(&self) -> &IndexSet<N> {
+        &self.raw
+    }
+    pub fn as_raw_mut(&mut self) -> &mut IndexSet<N> {
+        &mut self.raw
+    }
+    pub fn into_raw(self) -> IndexSet<N> {
+        self.raw
+    }
+    /// Adds the given ID to the set. Returns whether it was absent before i.e. the set has changed.
+    pub fn insert(&mut self, id: I) -> bool {
+        self.raw.insert(id.to_index())
+    }
+    /// Removes the given ID from the set. Returns whether it was present before i.e. the set has changed.
+    pub fn remove(&mut self, id: I) -> bool {
+        self.raw.remove(id.to_index())
+    }
+    pub fn contains(&self, id: I) -> bool {
+        self.raw.contains(id.to_index())
+    }
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.raw.clear()
+    }
+    /// Iterates over the IDs in ascending order of their indices.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = I> + '_ {
+        self.raw.iter().map(I::from_index)
+    }
+    /// Returns the ID with the least index.
+    pub fn min_element(&self) -> Option<I> {
+        self.raw.min_element().map(I::from_index)
+    }
+    /// Returns the ID with the greatest index.
+    pub fn max_element(&self) -> Option<I> {
+        self.raw.max_element().map(I::from_index)
+    }
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        self.raw.is_subset_of(&other.raw)
+    }
+    pub fn is_superset_of(&self, other: &Self) -> bool {
+        self.raw.is_superset_of(&other.raw)
+    }
+    pub fn is_disjoint_with(&self, other: &Self) -> bool {
+        self.raw.is_disjoint_with(&other.raw)
+    }
+    /// Equivalent to for id in other.iter() { self.insert(id); }
+    pub fn insert_all(&mut self, other: &Self) {
+        self.raw.insert_all(&other.raw)
+    }
+    /// Equivalent to for id in other.iter() { self.remove(id); }
+    pub fn remove_all(&mut self, other: &Self) {
+        self.raw.remove_all(&other.raw)
+    }
+}
+impl<I: Idx, const N: usize> Default for TypedIndexSet<I, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<I: Idx, const N: usize> Clone for TypedIndexSet<I, N> {
+    fn clone(&self) -> Self {
+        Self::from_raw(self.raw.clone())
+    }
+}
+impl<I: Idx, const N: usize> PartialEq for TypedIndexSet<I, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+impl<I: Idx, const N: usize> Eq for TypedIndexSet<I, N> {}
+impl<I: Idx, const N: usize> Hash for TypedIndexSet<I, N> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.raw.hash(hasher)
+    }
+}
+impl<I: Idx + Debug, const N: usize> Debug for TypedIndexSet<I, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+impl<I: Idx, const N: usize> FromIterator<I> for TypedIndexSet<I, N> {
+    fn from_iter<T: IntoIterator<Item = I>>(into_iter: T) -> Self {
+        Self::from_raw(into_iter.into_iter().map(I::to_index).collect())
+    }
+}
+impl<I: Idx, const N: usize> Extend<I> for TypedIndexSet<I, N> {
+    fn extend<T: IntoIterator<Item = I>>(&mut self, into_iter: T) {
+        for id in into_iter {
+            self.insert(id);
+        }
+    }
+}
+
+macro_rules! impl_typed_bin_op {
+    ($Trait:ident, $method:ident, $AssignTrait:ident, $assign_method:ident) => {
+        impl<I: Idx, const N: usize> $Trait<&TypedIndexSet<I, N>> for &TypedIndexSet<I, N> {
+            type Output = TypedIndexSet<I, N>;
+            fn $method(self, other: &TypedIndexSet<I, N>) -> TypedIndexSet<I, N> {
+                TypedIndexSet::from_raw((&self.raw).$method(&other.raw))
+            }
+        }
+        impl<I: Idx, const N: usize> $AssignTrait<&TypedIndexSet<I, N>> for TypedIndexSet<I, N> {
+            fn $assign_method(&mut self, other: &TypedIndexSet<I, N>) {
+                self.raw.$assign_method(&other.raw)
+            }
+        }
+    };
+}
+
+impl_typed_bin_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_typed_bin_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_typed_bin_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+impl_typed_bin_op!(Sub, sub, SubAssign, sub_assign);
@@ -0,0 +1,156 @@
+use super::Chunk;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Range, Shl, Shr,
+};
+
+/// An unsigned integer type in which an IndexSet stores its bits, e.g., u32 in `IndexSet<4, u32>`.
+/// ChunkRead and the combinators always read Chunks (i.e., usize), so sets storing other words
+/// convert between their words and chunks when read as ChunkReads.
+pub trait Word:
+    Copy
+    + Eq
+    + Ord
+    + Hash
+    + Debug
+    + Default
+    + Not<Output = Self>
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + BitAndAssign
+    + BitOrAssign
+    + BitXorAssign
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+    + 'static
+{
+    const BITS: u32;
+    const ZERO: Self;
+    const ONE: Self;
+    const MAX: Self;
+    fn count_ones(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+    fn leading_zeros(self) -> u32;
+    fn overflowing_add(self, rhs: Self) -> (Self, bool);
+    fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+    /// Returns the lowest usize::BITS bits, i.e., `self as Chunk`.
+    fn truncate_to_chunk(self) -> Chunk;
+    /// Returns the lowest Self::BITS bits of chunk, i.e., `chunk as Self`.
+    fn from_chunk_truncating(chunk: Chunk) -> Self;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            const BITS: u32 = <$t>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX: Self = <$t>::MAX;
+            fn count_ones(self) -> u32 {
+                <$t>::count_ones(self)
+            }
+            fn trailing_zeros(self) -> u32 {
+                <$t>::trailing_zeros(self)
+            }
+            fn leading_zeros(self) -> u32 {
+                <$t>::leading_zeros(self)
+            }
+            fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+                <$t>::overflowing_add(self, rhs)
+            }
+            fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+                <$t>::overflowing_sub(self, rhs)
+            }
+            fn truncate_to_chunk(self) -> Chunk {
+                self as Chunk
+            }
+            fn from_chunk_truncating(chunk: Chunk) -> Self {
+                chunk as Self
+            }
+        }
+    )*};
+}
+impl_word!(u8, u16, u32, u64, u128, usize);
+
+///////////////////////////////////////////////////////////////////////
+// Conversions between words and chunks. Whichever of the two is wider holds a whole number of the other.
+
+/// Returns true iff words of type W are narrower than chunks.
+fn is_narrow<W: Word>() -> bool {
+    W::BITS < usize::BITS
+}
+/// Number of words per chunk, if words are narrow.
+fn words_per_chunk<W: Word>() -> usize {
+    (usize::BITS / W::BITS) as usize
+}
+/// Number of chunks per word, if words are not narrow.
+fn chunks_per_word<W: Word>() -> usize {
+    (W::BITS / usize::BITS) as usize
+}
+
+/// Returns the number of chunks overlapping the given number of words.
+pub(crate) fn chunk_count_of_words<W: Word>(word_count: usize) -> usize {
+    if is_narrow::<W>() {
+        word_count.div_ceil(words_per_chunk::<W>())
+    } else {
+        word_count.saturating_mul(chunks_per_word::<W>())
+    }
+}
+/// Returns the indices of the words overlapping the chunk at idx_of_chunk.
+pub(crate) fn word_range_of_chunk<W: Word>(idx_of_chunk: usize) -> Range<usize> {
+    if is_narrow::<W>() {
+        let wpc = words_per_chunk::<W>();
+        let start = idx_of_chunk.saturating_mul(wpc);
+        start..start.saturating_add(wpc)
+    } else {
+        let idx_of_word = idx_of_chunk / chunks_per_word::<W>();
+        idx_of_word..idx_of_word + 1
+    }
+}
+/// Returns the index of the first chunk overlapping the word at idx_of_word.
+pub(crate) fn first_chunk_of_word<W: Word>(idx_of_word: usize) -> usize {
+    if is_narrow::<W>() {
+        idx_of_word / words_per_chunk::<W>()
+    } else {
+        idx_of_word.saturating_mul(chunks_per_word::<W>())
+    }
+}
+/// Returns the chunk at idx_of_chunk of the bits stored in words,
+/// or None if there are no words at or after that chunk.
+pub(crate) fn chunk_from_words<W: Word>(words: &[W], idx_of_chunk: usize) -> Option<Chunk> {
+    if is_narrow::<W>() {
+        let range = word_range_of_chunk::<W>(idx_of_chunk);
+        let (start, end) = (range.start, range.end.min(words.len()));
+        let words = words.get(start..end).filter(|words| !words.is_empty())?;
+        Some(
+            words.iter().enumerate().fold(0, |chunk, (j, &word)| {
+                chunk | word.truncate_to_chunk() << (j as u32 * W::BITS)
+            }),
+        )
+    } else {
+        let cpw = chunks_per_word::<W>();
+        let word = *words.get(idx_of_chunk / cpw)?;
+        Some((word >> ((idx_of_chunk % cpw) as u32 * usize::BITS)).truncate_to_chunk())
+    }
+}
+/// Replaces the bits of the chunk at idx_of_chunk with f of those bits.
+/// Bits beyond the end of words are treated as zero, and must remain so.
+pub(crate) fn update_chunk_in_words<W: Word, F: FnOnce(Chunk) -> Chunk>(
+    words: &mut [W],
+    idx_of_chunk: usize,
+    f: F,
+) {
+    let chunk = f(chunk_from_words(words, idx_of_chunk).unwrap_or(0));
+    if is_narrow::<W>() {
+        let range = word_range_of_chunk::<W>(idx_of_chunk);
+        for (j, word) in words.iter_mut().take(range.end).skip(range.start).enumerate() {
+            *word = W::from_chunk_truncating(chunk >> (j as u32 * W::BITS));
+        }
+    } else if let Some(word) = words.get_mut(idx_of_chunk / chunks_per_word::<W>()) {
+        let shift = (idx_of_chunk % chunks_per_word::<W>()) as u32 * usize::BITS;
+        let mask = W::from_chunk_truncating(!0) << shift;
+        *word = (*word & !mask) | W::from_chunk_truncating(chunk) << shift;
+    }
+}